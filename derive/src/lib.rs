@@ -1,363 +1,1589 @@
 #![forbid(unsafe_code)]
 use proc_macro::TokenStream;
-use proc_macro2::{Literal, Span};
-use quote::quote;
-use std::{default::Default, num::ParseIntError};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
 use syn::{
-    parse_str, punctuated::Punctuated, token::Comma, Attribute, DeriveInput, Expr, ExprLit, Field,
-    GenericParam, Ident, Lit, Type, TypePath,
+    parse_macro_input, parse_quote, punctuated::Punctuated, visit_mut::VisitMut, Attribute,
+    DeriveInput, Error, Expr, Field, Ident, Index, Lifetime, Member, Token, Type, TypePath,
+    WherePredicate,
 };
 
 const ATTR_SKIP: &str = "skip";
 const ATTR_WHEN: &str = "when";
 const ATTR_LEN: &str = "len";
+//#[size(expr)]: the field is received/sent within the bytes
+const ATTR_SIZE: &str = "size";
+//#[tag(field.kind)] or #[tag(field.kind, check)] on the enum field
+const ATTR_TAG: &str = "tag";
+//#[varint] on i16/i32/i64 field: ZigZag + LEB128
+const ATTR_VARINT: &str = "varint";
+//#[be] or #[le]: the field is sent with BE or LE, the type is not changed
+const ATTR_BE: &str = "be";
+const ATTR_LE: &str = "le";
+//#[bits(n)]: the consecutive #[bits] fields are packed into the smallest integer (u8 to u128)
+const ATTR_BITS: &str = "bits";
+//#[len_of(field)] or #[count_of(field)]: the length field is computed from the field when it's sent
+const ATTR_LEN_OF: &str = "len_of";
+const ATTR_COUNT_OF: &str = "count_of";
+const ARG_CHECK: &str = "check";
+const ATTR_RESEND: &str = "resend";
+//#[resend(other)] on the catch-all variant, the trailing Vec<u8> is the rest of the bytes (needs #[size] or a frame on a socket)
+const ARG_OTHER: &str = "other";
+//#[resend(bound = "T: Trait")] replaces the bounds of the type parameters
+const ARG_BOUND: &str = "bound";
+//#[resend(big)] or #[resend(little)] on the type: #[be] or #[le] for all fields (and the enum tag)
+const ARG_BIG: &str = "big";
+const ARG_LITTLE: &str = "little";
+//#[resend(borrow)] on the struct: RefReceivable only, for the fields borrowing with other types like Name<'a>
+const ARG_BORROW: &str = "borrow";
+//#[resend(lsb_first)]: the first #[bits] field is in the least significant bits (the most significant by default)
+const ARG_LSB_FIRST: &str = "lsb_first";
 
-//TODO: union
-
-#[proc_macro_derive(Snd, attributes(skip, when, len))]
+#[proc_macro_derive(
+    Snd,
+    attributes(
+        skip, when, len, size, tag, varint, be, le, bits, len_of, count_of, resend
+    )
+)]
 pub fn send(item: TokenStream) -> TokenStream {
-    let ast: DeriveInput = syn::parse(item).unwrap();
-    match ast.data {
-        syn::Data::Enum(_) => send_enum(&ast),
-        syn::Data::Union(_) => TokenStream::new(),
-        syn::Data::Struct(_) => send_struct(&ast),
-    }
+    let ast = parse_macro_input!(item as DeriveInput);
+    let gen = match &ast.data {
+        syn::Data::Enum(data) => send_enum(&ast, data),
+        syn::Data::Union(data) => Err(union_error(data)),
+        syn::Data::Struct(data) => send_struct(&ast, &data.fields),
+    };
+    gen.unwrap_or_else(Error::into_compile_error).into()
 }
 
-#[proc_macro_derive(Rcv, attributes(skip, when, len))]
+#[proc_macro_derive(
+    Rcv,
+    attributes(
+        skip, when, len, size, tag, varint, be, le, bits, len_of, count_of, resend
+    )
+)]
 pub fn receive(item: TokenStream) -> TokenStream {
-    let ast: DeriveInput = syn::parse(item).unwrap();
+    let ast = parse_macro_input!(item as DeriveInput);
     // eprintln!("{:#?}", &ast);
 
-    match ast.data {
-        syn::Data::Enum(_) => receive_enum(&ast),
-        syn::Data::Union(_) => TokenStream::new(),
-        syn::Data::Struct(_) => receive_struct(&ast),
-    }
+    receive_impls(&ast)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(
+    EncodedLen,
+    attributes(
+        skip, when, len, size, tag, varint, be, le, bits, len_of, count_of, resend
+    )
+)]
+pub fn encoded_len(item: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(item as DeriveInput);
+    let gen = match &ast.data {
+        syn::Data::Enum(data) => len_enum(&ast, data),
+        syn::Data::Union(data) => Err(union_error(data)),
+        syn::Data::Struct(data) => len_struct(&ast, &data.fields),
+    };
+    gen.unwrap_or_else(Error::into_compile_error).into()
 }
 
 #[inline]
-fn send_struct(ast: &syn::DeriveInput) -> TokenStream {
-    let id_name = &ast.ident;
-    let fields = get_named_fields(ast);
+fn union_error(data: &syn::DataUnion) -> Error {
+    Error::new_spanned(
+        data.union_token,
+        "resend doesn't support union, use struct or enum",
+    )
+}
 
-    let build_fields = fields.iter().map(|f| {
-        let name = &f.ident;
-        let transient = get_attr(&f.attrs, ATTR_SKIP);
+///Receivable, AsyncReceivable (with the "tokio" feature)
+/// or RefReceivable<'a> (structs with lifetime parameter)
+#[derive(Clone, Copy)]
+enum Mode<'a> {
+    Sync,
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    Async,
+    Borrow(&'a Lifetime),
+}
+
+impl Mode<'_> {
+    fn rcv(self) -> TokenStream2 {
+        match self {
+            Mode::Sync => quote! { resend::Rcv::rcv(reader)? },
+            Mode::Async => quote! { resend::AsyncRcv::rcv(reader).await? },
+            Mode::Borrow(_) => quote! { resend::RcvRef::rcv_ref(reader)? },
+        }
+    }
 
-        let attr_len = get_attr(&f.attrs, ATTR_LEN);
+    ///Receive the value of the type, for the wrappers of the field type like ZigZag
+    fn rcv_as(self, ty: &Type) -> TokenStream2 {
+        match self {
+            Mode::Sync => quote! { <#ty as resend::Receivable>::rcv_from(reader)? },
+            Mode::Async => quote! { <#ty as resend::AsyncReceivable>::rcv_from(reader).await? },
+            Mode::Borrow(lt) => quote! { <#ty as resend::RefReceivable<#lt>>::rcv_ref_from(reader)? },
+        }
+    }
 
-        let attr = get_attr(&f.attrs, ATTR_WHEN);
-        if transient.is_none() {
-            let ty = &f.ty;
-            match ty {
-                Type::Reference(_) => quote! {
-                    writer.snd(self.#name)?
-                },
-                Type::Path(ref p) if is_option(p) && attr.is_some() => {
-                    quote! {
-                        if let Some(ref v) = self.#name {
-                            //we don't check the "when" attribute here since it needs to use self.xx
-                            writer.snd(v)?
-                        }
+    ///Receive the value with BE<T> or LE<T> if the field has the byte order
+    fn rcv_ordered(self, ty: &Type, order: Option<&Ident>) -> TokenStream2 {
+        match order {
+            Some(o) => {
+                let v = self.rcv_as(&parse_quote! { resend::endian::#o<#ty> });
+                quote! { (#v).0 }
+            }
+            None => self.rcv(),
+        }
+    }
+
+    fn read_len(self, ty: &Type, len: TokenStream2) -> TokenStream2 {
+        match self {
+            Mode::Sync => quote! { <#ty as resend::FromReader>::from_reader(reader, #len as usize)? },
+            Mode::Async => quote! {
+                <#ty as resend::AsyncFromReader>::from_reader(reader, #len as usize).await?
+            },
+            Mode::Borrow(lt) => quote! {
+                <#ty as resend::RefFromReader<#lt>>::ref_from_reader(reader, #len as usize)?
+            },
+        }
+    }
+
+    ///All the bytes left, for the trailing Vec<u8> of the catch-all variant
+    fn rcv_rest(self) -> TokenStream2 {
+        match self {
+            Mode::Sync | Mode::Borrow(_) => quote! { resend::Receiver::rcv_rest(reader)? },
+            Mode::Async => quote! { resend::AsyncReceiver::rcv_rest(reader).await? },
+        }
+    }
+
+    fn skip_rest(self) -> TokenStream2 {
+        match self {
+            Mode::Sync | Mode::Borrow(_) => quote! { reader.skip_rest()? },
+            Mode::Async => quote! { reader.skip_rest_async().await? },
+        }
+    }
+
+    ///The field value with the path of the field in the error
+    fn rcv_field(self, path: &str, value: TokenStream2) -> TokenStream2 {
+        match self {
+            Mode::Sync | Mode::Borrow(_) => quote! {
+                resend::error::rcv_field(reader, #path, |reader| Ok(#value))?
+            },
+            //the async block for ? in the value, no async closure
+            Mode::Async => quote! {
+                {
+                    let offset = resend::AsyncReceiver::position(&*reader);
+                    let v: resend::Result<_> = async { Ok(#value) }.await;
+                    v.map_err(|e| e.in_field(#path, offset))?
+                }
+            },
+        }
+    }
+
+    fn read_tagged(self, ty: &Type, tag: TokenStream2) -> TokenStream2 {
+        match self {
+            Mode::Sync | Mode::Borrow(_) => quote! {
+                <#ty as resend::FromTag>::from_tag(reader, (#tag) as <#ty as resend::FromTag>::Tag)?
+            },
+            Mode::Async => quote! {
+                <#ty as resend::AsyncFromTag>::from_tag(
+                    reader,
+                    (#tag) as <#ty as resend::AsyncFromTag>::Tag,
+                ).await?
+            },
+        }
+    }
+
+    ///The bound of the type parameters
+    fn bound(self) -> TokenStream2 {
+        match self {
+            Mode::Sync => quote! { resend::Receivable },
+            Mode::Async => quote! { resend::AsyncReceivable },
+            Mode::Borrow(lt) => quote! { resend::RefReceivable<#lt> },
+        }
+    }
+
+    ///FromTag (AsyncFromTag) impl of enum, the body reads the variant with the tag
+    fn impl_from_tag(
+        self,
+        ast: &DeriveInput,
+        tag_type: &Ident,
+        body: TokenStream2,
+    ) -> syn::Result<TokenStream2> {
+        let id_name = &ast.ident;
+        let generics = bounded_generics(ast, self.bound())?;
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        Ok(match self {
+            Mode::Sync | Mode::Borrow(_) => quote! {
+                impl #impl_generics resend::FromTag for #id_name #ty_generics #where_clause {
+                    type Tag = #tag_type;
+
+                    #[inline]
+                    #[allow(unused_variables, clippy::needless_question_mark)]
+                    fn from_tag<R: resend::Receiver>(reader: &mut R, tag: #tag_type) -> resend::Result<Self> {
+                        #body
                     }
                 }
-                _ => {
-                    if let Some(v) = attr_len {
-                        let len_q = match get_attr_len(v) {
-                            AttrLen::Ident(id) => quote! {
-                                self.#id
-                            },
-                            AttrLen::LitInt(i) => quote! {
-                                #i
-                            },
-                            AttrLen::None => panic!("Invalid len attribute"),
-                        };
-                        quote! {
-                            resend::IntoWriter::into_writer(&self.#name, writer, #len_q as usize)?;
-                        }
-                    } else {
-                        quote! {
-                            writer.snd(&(self.#name))?
-                        }
+            },
+            Mode::Async => quote! {
+                impl #impl_generics resend::AsyncFromTag for #id_name #ty_generics #where_clause {
+                    type Tag = #tag_type;
+
+                    #[inline]
+                    #[allow(unused_variables, clippy::needless_question_mark)]
+                    async fn from_tag<R: resend::AsyncReceiver>(reader: &mut R, tag: #tag_type) -> resend::Result<Self> {
+                        #body
                     }
                 }
-            }
-        } else {
-            Default::default() //empty TokenStream
+            },
+        })
+    }
+
+    fn rcv_untagged(self, tag_type: &Ident, order: Option<&Ident>) -> TokenStream2 {
+        let tag = self.rcv_ordered(&parse_quote! { #tag_type }, order);
+        match self {
+            Mode::Sync | Mode::Borrow(_) => quote! {
+                let tag: #tag_type = #tag;
+                <Self as resend::FromTag>::from_tag(reader, tag)
+            },
+            Mode::Async => quote! {
+                let tag: #tag_type = #tag;
+                <Self as resend::AsyncFromTag>::from_tag(reader, tag).await
+            },
         }
-    });
+    }
 
-    let generics = get_lifetime(ast);
+    fn impl_receivable(self, ast: &DeriveInput, body: TokenStream2) -> syn::Result<TokenStream2> {
+        let id_name = &ast.ident;
+        let generics = bounded_generics(ast, self.bound())?;
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        Ok(match self {
+            Mode::Sync => quote! {
+                impl #impl_generics resend::Receivable for #id_name #ty_generics #where_clause {
+                    #[inline]
+                    #[allow(unused_variables, clippy::needless_question_mark)]
+                    fn rcv_from<R: resend::Receiver>(reader: &mut R) -> resend::Result<Self> {
+                        #body
+                    }
+                }
+            },
+            Mode::Async => quote! {
+                impl #impl_generics resend::AsyncReceivable for #id_name #ty_generics #where_clause {
+                    #[inline]
+                    #[allow(unused_variables, clippy::needless_question_mark)]
+                    async fn rcv_from<R: resend::AsyncReceiver>(reader: &mut R) -> resend::Result<Self> {
+                        #body
+                    }
+                }
+            },
+            Mode::Borrow(lt) => quote! {
+                impl #impl_generics resend::RefReceivable<#lt> for #id_name #ty_generics #where_clause {
+                    #[inline]
+                    #[allow(unused_variables, clippy::needless_question_mark)]
+                    fn rcv_ref_from<R: resend::RefReceiver<#lt>>(reader: &mut R) -> resend::Result<Self> {
+                        #body
+                    }
+                }
+            },
+        })
+    }
+}
 
-    let life = quote! {
-        <#(#generics,)*>
-    };
+#[inline]
+fn send_struct(ast: &syn::DeriveInput, fields: &syn::Fields) -> syn::Result<TokenStream2> {
+    let id_name = &ast.ident;
+    let is_tuple = matches!(fields, syn::Fields::Unnamed(_));
+
+    let build_fields = send_fields(
+        &field_infos(fields, &ast.attrs)?,
+        |fi| {
+            let name = &fi.member;
+            quote! { self.#name }
+        },
+        |id| {
+            let id = self_member(id, is_tuple);
+            quote! { self.#id }
+        },
+    )?;
+
+    let generics = bounded_generics(ast, quote! { resend::Sendable })?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let gen = quote! {
-        impl#life resend::Sendable for &#id_name#life {
+        impl #impl_generics resend::Sendable for #id_name #ty_generics #where_clause {
             #[inline]
+            #[allow(unused_variables)]
             fn snd_to<S: resend::Sender>(&self, writer: &mut S) -> resend::Result<()> {
                 #(#build_fields;)*
                 Ok(())
             }
         }
 
+        impl #impl_generics resend::Sendable for &#id_name #ty_generics #where_clause {
+            #[inline]
+            fn snd_to<S: resend::Sender>(&self, writer: &mut S) -> resend::Result<()> {
+                resend::Sendable::snd_to(*self, writer)
+            }
+        }
+
     };
-    gen.into()
+    Ok(gen)
 }
 #[inline]
-fn send_enum(ast: &syn::DeriveInput) -> TokenStream {
-    if let syn::Data::Enum(data) = &ast.data {
-        let id = &ast.ident;
-        let mut tag_value = 0;
+fn send_enum(ast: &syn::DeriveInput, data: &syn::DataEnum) -> syn::Result<TokenStream2> {
+    let id = &ast.ident;
+    let (tag_type, tags, tag_consts) = enum_tags(ast, data)?;
+    get_other_variant(data)?;
+    let order = type_order(&ast.attrs)?;
 
-        let mut tag_type = Ident::new("u8", Span::call_site());
-        if let Some(attr) = get_attr(&ast.attrs, "repr") {
-            if let Some(meta) = get_attr_meta(attr) {
-                tag_type = meta;
-            }
+    //the fields are bound to references with the variable names
+    let mut tag_arms = Vec::with_capacity(tags.len());
+    let mut arms = Vec::with_capacity(tags.len());
+    for (va, tmp_value) in data.variants.iter().zip(&tags) {
+        let id_item = &va.ident;
+        let infos = field_infos(&va.fields, &ast.attrs)?;
+        let pattern = construct(quote! { #id::#id_item }, &va.fields, &infos);
+
+        //the first field of the catch-all variant is the tag, the trailing bytes are sent as they are
+        let (tag_value, infos, rest) = if has_resend_arg(&va.attrs, ARG_OTHER) {
+            let name = &infos[0].var;
+            let (fields, rest) = other_fields(&infos);
+            (quote! { *#name }, fields, rest)
+        } else {
+            (quote! { #tmp_value }, &infos[..], None)
         };
 
-        let arms = data.variants.iter().map(|va| {
-            let id_item = &va.ident;
-            if let Some((
-                _,
-                Expr::Lit(ExprLit {
-                    lit: Lit::Int(v), ..
-                }),
-            )) = &va.discriminant
-            {
-                tag_value = parse_int(&v.to_string()).unwrap();
+        let mut build_fields = send_fields(
+            infos,
+            |fi| {
+                let name = &fi.var;
+                quote! { (*#name) }
+            },
+            |id| quote! { (*#id) },
+        )?;
+        if let Some(fi) = rest {
+            let name = &fi.var;
+            build_fields.push(quote! { resend::Sender::snd_all(writer, &(*#name))? });
+        }
+
+        tag_arms.push(quote! {
+            #pattern => #tag_value
+        });
+        arms.push(quote! {
+            #pattern => {
+                #(#build_fields;)*
             }
+        });
+    }
 
-            let path = if let syn::Fields::Unnamed(f) = &va.fields {
-                if let Some(ff) = f.unnamed.first() {
-                    if let syn::Type::Path(p) = &ff.ty {
-                        Some(p)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+    let snd_tag = snd_ordered(
+        quote! { resend::IntoTag::tag(self) },
+        order.map(|o| Ident::new(o, Span::call_site())).as_ref(),
+    );
+    let generics = bounded_generics(ast, quote! { resend::Sendable })?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let gen = quote! {
+        impl #impl_generics resend::IntoTag for #id #ty_generics #where_clause {
+            type Tag = #tag_type;
+
+            #[inline]
+            #[allow(unused_variables)]
+            fn tag(&self) -> #tag_type {
+                #tag_consts
+                match self {
+                    #(#tag_arms,)*
                 }
-            } else {
-                None
-            };
+            }
 
-            let tmp_value = if tag_type == "u8" {
-                Literal::u8_suffixed(tag_value as u8)
-            } else if tag_type == "u16" {
-                Literal::u16_suffixed(tag_value as u16)
-            } else {
-                Literal::u32_suffixed(tag_value)
-            };
+            #[inline]
+            #[allow(unused_variables)]
+            fn snd_untagged<S: resend::Sender>(&self, writer: &mut S) -> resend::Result<()> {
+                match self {
+                    #(#arms,)*
+                }
+                Ok(())
+            }
+        }
+
+        impl #impl_generics resend::Sendable for #id #ty_generics #where_clause {
+            #[inline]
+            fn snd_to<S: resend::Sender>(&self, writer: &mut S) -> resend::Result<()> {
+                #snd_tag;
+                resend::IntoTag::snd_untagged(self, writer)
+            }
+        }
+
+        impl #impl_generics resend::Sendable for &#id #ty_generics #where_clause {
+            #[inline]
+            fn snd_to<S: resend::Sender>(&self, writer: &mut S) -> resend::Result<()> {
+                resend::Sendable::snd_to(*self, writer)
+            }
+        }
+    };
+
+    Ok(gen)
+}
+
+const INT_TYPES: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
 
-            tag_value += 1;
+///The tag type from #[repr] (u8 by default) and the const tag value of every variant.
+/// The discriminants (literals or const expressions) are evaluated by the compiler:
+/// const __RESEND_TAG_0: u16 = 2; const __RESEND_TAG_1: u16 = __RESEND_TAG_0 + 1;
+#[inline]
+fn enum_tags(
+    ast: &syn::DeriveInput,
+    data: &syn::DataEnum,
+) -> syn::Result<(Ident, Vec<Ident>, TokenStream2)> {
+    let tag_type = get_repr_int(&ast.attrs)?;
+
+    let mut tags: Vec<Ident> = Vec::with_capacity(data.variants.len());
+    let consts = data.variants.iter().enumerate().map(|(i, va)| {
+        let name = format_ident!("__RESEND_TAG_{}", i);
+        let value = match (&va.discriminant, tags.last()) {
+            (Some((_, exp)), _) => quote! { #exp },
+            (None, Some(prev)) => quote! { #prev + 1 },
+            (None, None) => quote! { 0 },
+        };
+        tags.push(name.clone());
+        quote! {
+            const #name: #tag_type = #value;
+        }
+    });
+    let consts = quote! { #(#consts)* };
+
+    Ok((tag_type, tags, consts))
+}
 
-            if path.is_some() {
+///Send the fields of struct or enum variant.
+/// value: the field value, len_value: the value of the field (variable) in #[len(expr)]
+#[inline]
+fn send_fields(
+    infos: &[FieldInfo],
+    value: impl Fn(&FieldInfo) -> TokenStream2,
+    len_value: impl Fn(&Ident) -> TokenStream2,
+) -> syn::Result<Vec<TokenStream2>> {
+    //the #[len_of] fields are computed first, the other fields use the computed values
+    let mut build_fields: Vec<TokenStream2> = infos
+        .iter()
+        .filter_map(|fi| {
+            let len_of = fi.len_of.as_ref()?;
+            let ty = &fi.field.ty;
+            let var = len_of_var(&fi.var);
+            let len = len_of_value(len_of, infos, &len_value);
+            Some(len.map(|len| {
                 quote! {
-                    #id::#id_item(v) => {
-                        writer.snd(#tmp_value)?;
-                        writer.snd(v)?;
+                    let #var = {
+                        let len: usize = #len;
+                        <#ty as core::convert::TryFrom<usize>>::try_from(len).map_err(|_| {
+                            resend::error::Error::DataTooLarge {
+                                len,
+                                max: <#ty>::MAX as usize,
+                            }
+                        })?
                     }
                 }
-            } else {
+            }))
+        })
+        .collect::<syn::Result<_>>()?;
+    let len_value = with_len_of(infos, len_value);
+    for (i, fi) in infos.iter().enumerate() {
+        if get_attr(&fi.field.attrs, ATTR_SKIP).is_some() {
+            continue;
+        }
+        //the packed integer is sent with the first #[bits] field
+        if let Some(bits) = &fi.bits {
+            if let Some((ty, n)) = &bits.group {
+                build_fields.push(send_bits(&infos[i..i + n], ty, &value));
+            }
+            continue;
+        }
+        let snd = send_field(fi, value(fi), infos, &len_value)?;
+        build_fields.push(match get_attr(&fi.field.attrs, ATTR_SIZE) {
+            //exactly size bytes, padded with 0
+            Some(v) => {
+                let size = attr_len_value(v, infos, &len_value)?;
                 quote! {
-                    #id::#id_item => writer.snd(#tmp_value)?
+                    resend::bounded::snd_sized(writer, #size as usize, |writer| {
+                        #snd;
+                        Ok(())
+                    })?
                 }
             }
+            None => snd,
         });
+    }
+    Ok(build_fields)
+}
 
-        let gen = quote! {
-            impl resend::Sendable for &#id {
-                #[inline]
-                fn snd_to<S: resend::Sender>(&self, writer: &mut S) -> resend::Result<()> {
-                    match self {
-                        #(#arms,)*
-                    }
-                    Ok(())
+///Pack the #[bits] fields into the integer, Error::BitsOverflow if the value doesn't fit
+#[inline]
+fn send_bits(
+    group: &[FieldInfo],
+    ty: &Ident,
+    value: impl Fn(&FieldInfo) -> TokenStream2,
+) -> TokenStream2 {
+    let packs = group.iter().map(|fi| {
+        let Bits { width, shift, .. } = fi.bits.as_ref().unwrap();
+        let name = value(fi);
+        let field = member_name(fi);
+        let overflow = quote! {
+            return Err(resend::error::Error::BitsOverflow {
+                field: #field,
+                bits: #width,
+            });
+        };
+        //signed: two's complement in n bits, -4..=3 in 3 bits
+        if is_signed(&fi.field.ty) {
+            let sign = width - 1;
+            let mask = bits_mask(*width);
+            quote! {
+                let v = (#name) as i128;
+                if v >> #sign != 0 && v >> #sign != -1 {
+                    #overflow
                 }
+                __bits |= ((v as u128 & #mask) as #ty) << #shift;
             }
-        };
-
-        gen.into()
-    } else {
-        panic!("Enum expected");
+        } else {
+            quote! {
+                let v = (#name) as u128;
+                if v >> #width != 0 {
+                    #overflow
+                }
+                __bits |= (v as #ty) << #shift;
+            }
+        }
+    });
+    let snd = snd_ordered(quote! { __bits }, group[0].order.as_ref());
+    quote! {
+        {
+            let mut __bits: #ty = 0;
+            #(#packs)*
+            #snd
+        }
     }
 }
 
-//region receive
+///Send the field value (self.x)
 #[inline]
-fn receive_struct(ast: &syn::DeriveInput) -> TokenStream {
-    let id_name = &ast.ident;
-    let fields = get_named_fields(ast);
-
-    let mut names = Vec::with_capacity(fields.len());
-    let build_fields = fields.iter().map(|f| {
-        let name = &f.ident;
-        names.push(name);
-        let transient = get_attr(&f.attrs, ATTR_SKIP);
-        if transient.is_some() {
+fn send_field(
+    fi: &FieldInfo,
+    name: TokenStream2,
+    infos: &[FieldInfo],
+    len_value: impl Fn(&Ident) -> TokenStream2,
+) -> syn::Result<TokenStream2> {
+    let f = fi.field;
+    let attr_len = get_attr(&f.attrs, ATTR_LEN);
+    let ty = &f.ty;
+    if fi.len_of.is_some() {
+        let var = len_of_var(&fi.var);
+        return Ok(snd_ordered(quote! { #var }, fi.order.as_ref()));
+    }
+    if let Some(v) = get_attr(&f.attrs, ATTR_TAG) {
+        let (tag, check) = get_tag_args(v)?;
+        let tag = self_expr(tag, infos, &len_value);
+        let check = check.then(|| {
             quote! {
-                let #name = std::default::Default::default()
+                if tag != resend::IntoTag::tag(&#name) {
+                    return Err(resend::error::Error::InvalidTag {
+                        ty: stringify!(#ty),
+                        tag: tag as u64,
+                    });
+                }
             }
-        } else if let Some(attr) = get_attr(&f.attrs, ATTR_WHEN) {
-            let exp = get_when_args(attr);
-            quote! {
-                let #name = if #exp {
-                    Some(reader.rcv()?)
-                }else{
-                    None
+        });
+        //the tag is sent with another field, the block keeps tag from the later fields
+        return Ok(quote! {
+            {
+                let tag = (#tag) as <#ty as resend::IntoTag>::Tag;
+                #check
+                resend::IntoTag::snd_untagged(&#name, writer)?
+            }
+        });
+    }
+    if get_attr(&f.attrs, ATTR_VARINT).is_some() {
+        return Ok(match attr_len {
+            //padded to len bytes
+            Some(v) => {
+                let len_q = attr_len_value(v, infos, &len_value)?;
+                quote! {
+                    resend::IntoWriter::into_writer(&resend::endian::ZigZag(#name), writer, #len_q as usize)?
                 }
             }
-        } else if let Some(v) = get_attr(&f.attrs, ATTR_LEN) {
-            let len_q = match get_attr_len(v) {
-                AttrLen::Ident(id) => quote! {
-                    #id
-                },
-                AttrLen::LitInt(i) => quote! {
-                    #i
-                },
-                AttrLen::None => panic!("Invalid len attribute"),
-            };
+            None => quote! {
+                resend::Snd::snd(writer, resend::endian::ZigZag(#name))?
+            },
+        });
+    }
+    let order = fi.order.as_ref();
+    Ok(match get_attr(&f.attrs, ATTR_WHEN) {
+        Some(attr) => {
+            //the condition is checked with the values to send: self.x or (*x)
+            option_arg(ty)?;
+            let exp = self_expr(get_when_args(attr)?, infos, &len_value);
+            let field = member_name(fi);
+            let snd_v = snd_ordered(quote! { *v }, order);
+            //the block keeps condition from the later fields
             quote! {
-                let #name = resend::FromReader::from_reader(reader, #len_q as usize)?;
+                {
+                    let condition: bool = #exp;
+                    if condition != (#name).is_some() {
+                        return Err(resend::error::Error::WhenMismatch {
+                            field: #field,
+                            condition,
+                        });
+                    }
+                    if let Some(ref v) = #name {
+                        #snd_v
+                    }
+                }
             }
-        } else {
-            quote! {
-                let #name = reader.rcv()?
+        }
+        None => match attr_len {
+            Some(v) => {
+                let len_q = attr_len_value(v, infos, &len_value)?;
+                quote! {
+                    resend::IntoWriter::into_writer(&#name, writer, #len_q as usize)?;
+                }
             }
+            None => snd_ordered(name, order),
+        },
+    })
+}
+
+///The length of the field in #[len_of] or #[count_of] (elements) when it's sent.
+/// The #[len] field is counted the way #[len] reads it back (LenOf), the other fields in bytes as they are sent
+/// with their attributes: #[when] without the bool, #[varint] as ZigZag, #[tag] without the tag, #[size] without the padding.
+#[inline]
+fn len_of_value(
+    len_of: &LenOf,
+    infos: &[FieldInfo],
+    len_value: impl Fn(&Ident) -> TokenStream2,
+) -> syn::Result<TokenStream2> {
+    let value = len_value(&len_of.var);
+    if len_of.count {
+        return Ok(quote! { (#value).len() });
+    }
+    //checked by get_len_of
+    let target = infos.iter().find(|fi| fi.var == len_of.var).unwrap();
+    let attrs = &target.field.attrs;
+    if get_attr(attrs, ATTR_LEN).is_some() && get_attr(attrs, ATTR_VARINT).is_none() {
+        return Ok(quote! { resend::LenOf::len_of(&(#value)) });
+    }
+    //#[size] is usually the #[len_of] field itself: the value without the padding
+    if get_attr(attrs, ATTR_SIZE).is_some() {
+        return Ok(quote! { resend::EncodedLen::encoded_len(&(#value)) });
+    }
+    Ok(field_len(target, value, infos, &len_value)?.unwrap_or_else(|| quote! { 0 }))
+}
+
+///The variable of the computed #[len_of] field: __len_x
+#[inline]
+fn len_of_var(var: &Ident) -> Ident {
+    format_ident!("__len_{}", var)
+}
+
+///The field values in #[len], #[when] etc. with the computed #[len_of] fields
+#[inline]
+fn with_len_of<'a>(
+    infos: &'a [FieldInfo],
+    len_value: impl Fn(&Ident) -> TokenStream2 + 'a,
+) -> impl Fn(&Ident) -> TokenStream2 + 'a {
+    move |id: &Ident| {
+        if infos.iter().any(|fi| &fi.var == id && fi.len_of.is_some()) {
+            let var = len_of_var(id);
+            quote! { #var }
+        } else {
+            len_value(id)
         }
-    });
+    }
+}
 
-    let generics = get_lifetime(ast);
+///Send the value by reference, with BE(&v) or LE(&v) if the field has the byte order.
+/// T: Sendable is enough for the generic field without the order.
+#[inline]
+fn snd_ordered(value: TokenStream2, order: Option<&Ident>) -> TokenStream2 {
+    match order {
+        Some(o) => quote! {
+            resend::Snd::snd(writer, resend::endian::#o(&(#value)))?
+        },
+        None => quote! {
+            resend::Sendable::snd_to(&(#value), writer)?
+        },
+    }
+}
 
-    let life = quote! {
-        <#(#generics,)*>
+//region encoded_len
+#[inline]
+fn len_struct(ast: &syn::DeriveInput, fields: &syn::Fields) -> syn::Result<TokenStream2> {
+    let id_name = &ast.ident;
+    let is_tuple = matches!(fields, syn::Fields::Unnamed(_));
+
+    let lens = field_lens(
+        &field_infos(fields, &ast.attrs)?,
+        |fi| {
+            let name = &fi.member;
+            quote! { self.#name }
+        },
+        |id| {
+            let id = self_member(id, is_tuple);
+            quote! { self.#id }
+        },
+    )?;
+
+    let generics = bounded_generics(ast, quote! { resend::EncodedLen })?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let gen = quote! {
+        impl #impl_generics resend::EncodedLen for #id_name #ty_generics #where_clause {
+            #[inline]
+            fn encoded_len(&self) -> usize {
+                #lens
+            }
+        }
     };
+    Ok(gen)
+}
+
+#[inline]
+fn len_enum(ast: &syn::DeriveInput, data: &syn::DataEnum) -> syn::Result<TokenStream2> {
+    let id = &ast.ident;
+    let (tag_type, _, _) = enum_tags(ast, data)?;
+    get_other_variant(data)?;
+
+    let mut arms = Vec::with_capacity(data.variants.len());
+    for va in &data.variants {
+        let id_item = &va.ident;
+        let infos = field_infos(&va.fields, &ast.attrs)?;
+        let pattern = construct(quote! { #id::#id_item }, &va.fields, &infos);
 
+        //the tag field of the catch-all variant is counted as the tag, the trailing bytes without Length
+        let (infos, rest) = if has_resend_arg(&va.attrs, ARG_OTHER) {
+            other_fields(&infos)
+        } else {
+            (&infos[..], None)
+        };
+
+        let lens = field_lens(
+            infos,
+            |fi| {
+                let name = &fi.var;
+                quote! { (*#name) }
+            },
+            |id| quote! { (*#id) },
+        )?;
+        let rest = rest.map(|fi| {
+            let name = &fi.var;
+            quote! { + (*#name).len() }
+        });
+
+        arms.push(quote! {
+            #pattern => __tag_len + #lens #rest
+        });
+    }
+
+    let generics = bounded_generics(ast, quote! { resend::EncodedLen })?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let gen = quote! {
-        impl#life resend::Receivable for #id_name#life {
+        impl #impl_generics resend::EncodedLen for #id #ty_generics #where_clause {
             #[inline]
-            fn rcv_from<R: resend::Receiver>(reader: &mut R) -> resend::Result<Self> {
-                #(#build_fields;)*
+            #[allow(unused_variables)]
+            fn encoded_len(&self) -> usize {
+                let __tag_len = resend::EncodedLen::encoded_len(&(0 as #tag_type));
+                match self {
+                    #(#arms,)*
+                }
+            }
+        }
+    };
+    Ok(gen)
+}
 
-                Ok(
-                    #id_name{
-                        #(#names,)*
-                    }
-                )
+///The encoded length of every field, the same as send_fields
+#[inline]
+fn field_lens(
+    infos: &[FieldInfo],
+    value: impl Fn(&FieldInfo) -> TokenStream2,
+    len_value: impl Fn(&Ident) -> TokenStream2,
+) -> syn::Result<TokenStream2> {
+    //the computed #[len_of] fields, the same as send_fields
+    let len_ofs = infos.iter().filter_map(|fi| {
+        let len_of = fi.len_of.as_ref()?;
+        let ty = &fi.field.ty;
+        let var = len_of_var(&fi.var);
+        let len = len_of_value(len_of, infos, &len_value);
+        //checked like send_fields, which fails with DataTooLarge if it doesn't fit
+        Some(len.map(|len| {
+            quote! {
+                #[allow(unused_variables)]
+                let #var = <#ty as core::convert::TryFrom<usize>>::try_from(#len).unwrap_or(<#ty>::MAX);
+            }
+        }))
+    });
+    let len_ofs: Vec<TokenStream2> = len_ofs.collect::<syn::Result<_>>()?;
+    let len_value = with_len_of(infos, len_value);
+    let mut lens = Vec::with_capacity(infos.len());
+    for fi in infos {
+        if let Some(len) = field_len(fi, value(fi), infos, &len_value)? {
+            lens.push(len);
+        }
+    }
+    Ok(quote! {
+        {
+            #(#len_ofs)*
+            0 #(+ #lens)*
+        }
+    })
+}
+
+///The encoded length of the field value (self.x) with its attributes, None if it isn't sent
+#[inline]
+fn field_len(
+    fi: &FieldInfo,
+    name: TokenStream2,
+    infos: &[FieldInfo],
+    len_value: impl Fn(&Ident) -> TokenStream2,
+) -> syn::Result<Option<TokenStream2>> {
+    let f = fi.field;
+    if get_attr(&f.attrs, ATTR_SKIP).is_some() {
+        return Ok(None);
+    }
+    Ok(Some(if let Some(bits) = &fi.bits {
+        match &bits.group {
+            Some((ty, _)) => quote! { core::mem::size_of::<#ty>() },
+            None => return Ok(None),
+        }
+    } else if let Some(v) = get_attr(&f.attrs, ATTR_SIZE) {
+        let size = attr_len_value(v, infos, &len_value)?;
+        quote! { #size as usize }
+    } else if get_attr(&f.attrs, ATTR_TAG).is_some() {
+        //sent without the tag
+        quote! {
+            (resend::EncodedLen::encoded_len(&(#name))
+                - resend::EncodedLen::encoded_len(&resend::IntoTag::tag(&(#name))))
+        }
+    } else if get_attr(&f.attrs, ATTR_VARINT).is_some() {
+        match get_attr(&f.attrs, ATTR_LEN) {
+            Some(v) => {
+                let len_q = attr_len_value(v, infos, &len_value)?;
+                quote! {
+                    resend::IntoWriter::encoded_len_with(&resend::endian::ZigZag(#name), #len_q as usize)
+                }
+            }
+            None => quote! {
+                resend::EncodedLen::encoded_len(&resend::endian::ZigZag(#name))
+            },
+        }
+    } else if get_attr(&f.attrs, ATTR_WHEN).is_some() {
+        //no bool header
+        option_arg(&f.ty)?;
+        quote! {
+            (#name).as_ref().map_or(0, resend::EncodedLen::encoded_len)
+        }
+    } else if let Some(v) = get_attr(&f.attrs, ATTR_LEN) {
+        let len_q = attr_len_value(v, infos, &len_value)?;
+        quote! {
+            resend::IntoWriter::encoded_len_with(&(#name), #len_q as usize)
+        }
+    } else {
+        quote! {
+            resend::EncodedLen::encoded_len(&(#name))
+        }
+    }))
+}
+//endregion
+
+//region receive
+///Receivable (and AsyncReceivable), or RefReceivable<'a> of the structs borrowing from the buffer:
+/// a field has the reference &'a or #[resend(borrow)] on the struct
+#[inline]
+fn receive_impls(ast: &syn::DeriveInput) -> syn::Result<TokenStream2> {
+    if let syn::Data::Struct(data) = &ast.data {
+        if let Some(lt) = get_first_lifetime(ast) {
+            if has_resend_arg(&ast.attrs, ARG_BORROW)
+                || data.fields.iter().any(|f| borrows(&f.ty, lt))
+            {
+                return receive_with(ast, Mode::Borrow(lt));
             }
         }
+    }
+    let gen = receive_with(ast, Mode::Sync)?;
+
+    #[cfg(feature = "tokio")]
+    let gen = {
+        let gen_async = receive_with(ast, Mode::Async)?;
+        quote! {
+            #gen
+            #gen_async
+        }
+    };
+
+    Ok(gen)
+}
+
+#[inline]
+fn receive_with(ast: &syn::DeriveInput, mode: Mode) -> syn::Result<TokenStream2> {
+    match &ast.data {
+        syn::Data::Enum(data) => receive_enum(ast, data, mode),
+        syn::Data::Union(data) => Err(union_error(data)),
+        syn::Data::Struct(data) => receive_struct(ast, &data.fields, mode),
+    }
+}
+
+#[inline]
+fn receive_struct(
+    ast: &syn::DeriveInput,
+    fields: &syn::Fields,
+    mode: Mode,
+) -> syn::Result<TokenStream2> {
+    let id_name = &ast.ident;
+    let infos = field_infos(fields, &ast.attrs)?;
 
+    let build_fields = receive_fields(&infos, &id_name.to_string(), mode)?;
+
+    let value = construct(quote! { #id_name }, fields, &infos);
+    let body = quote! {
+        #(#build_fields;)*
+
+        Ok(
+            #value
+        )
     };
-    gen.into()
+    mode.impl_receivable(ast, body)
 }
 
 #[inline]
-fn receive_enum(ast: &syn::DeriveInput) -> TokenStream {
-    if let syn::Data::Enum(data) = &ast.data {
-        let id = &ast.ident;
-        let mut tag_value = 0;
+fn receive_enum(
+    ast: &syn::DeriveInput,
+    data: &syn::DataEnum,
+    mode: Mode,
+) -> syn::Result<TokenStream2> {
+    let id = &ast.ident;
+    let (tag_type, tags, tag_consts) = enum_tags(ast, data)?;
+    let order = type_order(&ast.attrs)?;
 
-        let mut tag_type = Ident::new("u8", Span::call_site());
-        if let Some(attr) = get_attr(&ast.attrs, "repr") {
-            if let Some(meta) = get_attr_meta(attr) {
-                tag_type = meta;
+    let mut arms = Vec::with_capacity(tags.len());
+    for (va, tmp_value) in data.variants.iter().zip(&tags) {
+        if has_resend_arg(&va.attrs, ARG_OTHER) {
+            continue;
+        }
+        let id_item = &va.ident;
+
+        let infos = field_infos(&va.fields, &ast.attrs)?;
+        let build_fields = receive_fields(&infos, &format!("{}::{}", id, id_item), mode)?;
+        let value = construct(quote! { #id::#id_item }, &va.fields, &infos);
+        arms.push(quote! {
+            #tmp_value => {
+                #(#build_fields;)*
+                Ok(#value)
             }
-        };
+        });
+    }
 
-        let arms = data.variants.iter().map(|va| {
-            let id_item = &va.ident;
-            if let Some((
-                _,
-                Expr::Lit(ExprLit {
-                    lit: Lit::Int(v), ..
-                }),
-            )) = &va.discriminant
-            {
-                tag_value = parse_int(&v.to_string()).unwrap();
+    //unknown tag: the catch-all variant or error
+    let other_arm = if let Some(va) = get_other_variant(data)? {
+        let id_item = &va.ident;
+        let infos = field_infos(&va.fields, &ast.attrs)?;
+        let name = &infos[0].var;
+        let ty = &infos[0].field.ty;
+        let path = format!("{}::{}", id, id_item);
+        let (fields, rest) = other_fields(&infos);
+        let mut build_fields = receive_fields(fields, &path, mode)?;
+        if let Some(fi) = rest {
+            let name = &fi.var;
+            let ty = &fi.field.ty;
+            let value = mode.rcv_field(&format!("{}.{}", path, member_name(fi)), mode.rcv_rest());
+            build_fields.push(quote! {
+                let #name: #ty = #value
+            });
+        }
+        let value = construct(quote! { #id::#id_item }, &va.fields, &infos);
+        quote! {
+            _ => {
+                let #name: #ty = tag;
+                #(#build_fields;)*
+                Ok(#value)
             }
+        }
+    } else {
+        quote! {
+            _ => Err(resend::error::Error::InvalidTag {
+                ty: stringify!(#id),
+                tag: tag as u64,
+            })
+        }
+    };
 
-            let path = if let syn::Fields::Unnamed(f) = &va.fields {
-                if let Some(ff) = f.unnamed.first() {
-                    if let syn::Type::Path(p) = &ff.ty {
-                        Some(p)
-                    } else {
-                        None
-                    }
-                } else {
+    let from_tag = mode.impl_from_tag(
+        ast,
+        &tag_type,
+        quote! {
+            #tag_consts
+            match tag {
+                #(#arms,)*
+                #other_arm,
+            }
+        },
+    )?;
+    let order = order.map(|o| Ident::new(o, Span::call_site()));
+    let receivable = mode.impl_receivable(ast, mode.rcv_untagged(&tag_type, order.as_ref()))?;
+
+    Ok(quote! {
+        #from_tag
+        #receivable
+    })
+}
+
+///Receive the fields of struct or enum variant to the variables, the errors have the path: Type.field or Enum::Variant.0
+#[inline]
+fn receive_fields(infos: &[FieldInfo], path: &str, mode: Mode) -> syn::Result<Vec<TokenStream2>> {
+    let mut build_fields = Vec::with_capacity(infos.len());
+    for (i, fi) in infos.iter().enumerate() {
+        //all #[bits] fields are unpacked with the first one
+        if let Some(bits) = &fi.bits {
+            if let Some((ty, n)) = &bits.group {
+                let path = format!("{}.{}", path, member_name(fi));
+                build_fields.push(receive_bits(&infos[i..i + n], ty, &path, mode));
+            }
+            continue;
+        }
+        let f = fi.field;
+        let name = &fi.var;
+        let ty = &f.ty;
+        let transient = get_attr(&f.attrs, ATTR_SKIP);
+        let value = if transient.is_some() {
+            quote! {
+                core::default::Default::default()
+            }
+        } else if let Some(attr) = get_attr(&f.attrs, ATTR_TAG) {
+            //the tag is from the field received before
+            let (tag, _) = get_tag_args(attr)?;
+            mode.read_tagged(ty, quote! { #tag })
+        } else if get_attr(&f.attrs, ATTR_VARINT).is_some() {
+            let zigzag: Type = parse_quote! { resend::endian::ZigZag<#ty> };
+            let v = match get_attr(&f.attrs, ATTR_LEN) {
+                Some(v) => mode.read_len(&zigzag, attr_len_value(v, infos, |id| quote! { #id })?),
+                None => mode.rcv_as(&zigzag),
+            };
+            quote! { (#v).0 }
+        } else if let Some(attr) = get_attr(&f.attrs, ATTR_WHEN) {
+            let exp = get_when_args(attr)?;
+            let arg = option_arg(ty)?;
+            let rcv = mode.rcv_ordered(arg, fi.order.as_ref());
+            quote! {
+                if #exp {
+                    Some(#rcv)
+                }else{
                     None
                 }
-            } else {
-                None
-            };
-            let tmp_value = if tag_type == "u8" {
-                Literal::u8_unsuffixed(tag_value as u8)
-            } else if tag_type == "u16" {
-                Literal::u16_unsuffixed(tag_value as u16)
-            } else {
-                Literal::u32_unsuffixed(tag_value)
-            };
+            }
+        } else if let Some(v) = get_attr(&f.attrs, ATTR_LEN) {
+            let len_q = attr_len_value(v, infos, |id| quote! { #id })?;
+            mode.read_len(ty, len_q)
+        } else {
+            mode.rcv_ordered(ty, fi.order.as_ref())
+        };
 
-            tag_value += 1;
+        if transient.is_some() {
+            build_fields.push(quote! {
+                let #name: #ty = #value
+            });
+            continue;
+        }
 
-            if let Some(p) = path {
+        //the field is received within the size, the rest is skipped
+        let value = match get_attr(&f.attrs, ATTR_SIZE) {
+            Some(v) => {
+                let size = attr_len_value(v, infos, |id| quote! { #id })?;
+                let skip_rest = mode.skip_rest();
                 quote! {
-                    #tmp_value => {
-                        let t: #p = reader.rcv()?;
-                        Ok(#id::#id_item(t))
+                    {
+                        let reader = &mut resend::bounded::Bounded::new(&mut *reader, #size as usize);
+                        let v: #ty = #value;
+                        #skip_rest;
+                        v
                     }
                 }
-            } else {
-                quote! {
-                    #tmp_value => Ok(#id::#id_item)
-                }
             }
+            None => value,
+        };
+
+        let value = mode.rcv_field(&format!("{}.{}", path, member_name(fi)), value);
+        build_fields.push(quote! {
+            let #name: #ty = #value
         });
+    }
+    Ok(build_fields)
+}
 
-        let gen = quote! {
-            impl resend::Receivable for #id {
-                #[inline]
-                fn rcv_from<R: resend::Receiver>(reader: &mut R) -> resend::Result<Self> {
-                    let tag: #tag_type = reader.rcv()?;
-                    match tag {
-                        #(#arms,)*
-                        _ => Err(resend::error::Error::InvalidTag(tag as u32)),
-                    }
-                }
+///Receive the packed integer and unpack the #[bits] fields to the variables, bool is true if the bits aren't 0
+#[inline]
+fn receive_bits(group: &[FieldInfo], ty: &Ident, path: &str, mode: Mode) -> TokenStream2 {
+    let rcv = mode.rcv_ordered(&parse_quote! { #ty }, group[0].order.as_ref());
+    let rcv = mode.rcv_field(path, rcv);
+    let unpacks = group.iter().map(|fi| {
+        let Bits { width, shift, .. } = fi.bits.as_ref().unwrap();
+        let name = &fi.var;
+        let fty = &fi.field.ty;
+        let mask = bits_mask(*width);
+        let v = quote! { ((__bits >> #shift) & #mask) };
+        match fty {
+            Type::Path(p) if p.path.is_ident("bool") => quote! { let #name: #fty = #v != 0 },
+            //sign-extended from the top bit
+            _ if is_signed(fty) => {
+                let ext = 128 - width;
+                quote! { let #name: #fty = ((#v as i128) << #ext >> #ext) as #fty }
             }
-        };
+            _ => quote! { let #name: #fty = #v as #fty },
+        }
+    });
+    quote! {
+        let __bits: #ty = #rcv;
+        #(#unpacks);*
+    }
+}
 
-        gen.into()
-    } else {
-        panic!("Enum expected");
+///Struct/variant expression or pattern with the field variables: Name{a, b}, Name(_0, _1) or Name
+#[inline]
+fn construct(path: TokenStream2, fields: &syn::Fields, infos: &[FieldInfo]) -> TokenStream2 {
+    let names = infos.iter().map(|fi| &fi.var);
+    match fields {
+        syn::Fields::Named(_) => quote! { #path{ #(#names,)* } },
+        syn::Fields::Unnamed(_) => quote! { #path( #(#names,)* ) },
+        syn::Fields::Unit => quote! { #path },
     }
 }
 
 //endregion
 
+///Field with the member to access it (self.x or self.0) and the variable name when it's received.
+/// The variable names of positional fields are _0, _1 etc. which can be used in #[len] and #[when].
+/// order: BE or LE from #[be]/#[le] or the type.
+struct FieldInfo<'a> {
+    field: &'a Field,
+    member: Member,
+    var: Ident,
+    order: Option<Ident>,
+    bits: Option<Bits>,
+    len_of: Option<LenOf>,
+}
+
+///#[len_of(field)] or #[count_of(field)]: the variable of the field and if the elements are counted
+struct LenOf {
+    var: Ident,
+    count: bool,
+}
+
+///#[bits(n)] field: width bits at the shift in the packed integer.
+/// The first one of the consecutive #[bits] fields has the group: the integer type and the number of the fields.
+struct Bits {
+    width: u32,
+    shift: u32,
+    group: Option<(Ident, usize)>,
+}
+
+///The fields of struct or enum variant, attrs: the attributes of the type
 #[inline]
-fn get_named_fields(ast: &syn::DeriveInput) -> &Punctuated<Field, Comma> {
-    if let syn::Data::Struct(syn::DataStruct {
-        fields: syn::Fields::Named(syn::FieldsNamed { ref named, .. }),
-        ..
-    }) = ast.data
-    {
-        named
-    } else {
-        panic!("Struct only")
+fn field_infos<'a>(
+    fields: &'a syn::Fields,
+    attrs: &[Attribute],
+) -> syn::Result<Vec<FieldInfo<'a>>> {
+    let order = type_order(attrs)?;
+    let mut infos = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let order = field_order(field, order)?;
+            Ok(match &field.ident {
+                Some(id) => FieldInfo {
+                    field,
+                    member: Member::Named(id.clone()),
+                    var: id.clone(),
+                    order,
+                    bits: None,
+                    len_of: None,
+                },
+                None => FieldInfo {
+                    field,
+                    member: Member::Unnamed(Index::from(i)),
+                    var: format_ident!("_{}", i),
+                    order,
+                    bits: None,
+                    len_of: None,
+                },
+            })
+        })
+        .collect::<syn::Result<Vec<FieldInfo>>>()?;
+    let len_ofs = infos
+        .iter()
+        .map(|fi| get_len_of(fi.field, &infos))
+        .collect::<syn::Result<Vec<_>>>()?;
+    for (fi, len_of) in infos.iter_mut().zip(len_ofs) {
+        fi.len_of = len_of;
+    }
+    pack_bits(&mut infos, has_resend_arg(attrs, ARG_LSB_FIRST))?;
+    Ok(infos)
+}
+
+///The field in #[len_of(field)] or #[count_of(field)], it's a field of the same struct or variant
+#[inline]
+fn get_len_of(f: &Field, infos: &[FieldInfo]) -> syn::Result<Option<LenOf>> {
+    let (attr, count) = match (
+        get_attr(&f.attrs, ATTR_LEN_OF),
+        get_attr(&f.attrs, ATTR_COUNT_OF),
+    ) {
+        (Some(_), Some(attr)) => {
+            return Err(Error::new_spanned(
+                attr,
+                "#[len_of] and #[count_of] can't be used together",
+            ))
+        }
+        (Some(attr), None) => (attr, false),
+        (None, Some(attr)) => (attr, true),
+        (None, None) => return Ok(None),
+    };
+    let own = [
+        ATTR_SKIP,
+        ATTR_WHEN,
+        ATTR_LEN,
+        ATTR_SIZE,
+        ATTR_VARINT,
+        ATTR_TAG,
+        ATTR_BITS,
+    ];
+    if own.iter().any(|&a| get_attr(&f.attrs, a).is_some()) {
+        return Err(Error::new_spanned(
+            attr,
+            "#[len_of] and #[count_of] can't be used with #[skip], #[when], #[len], #[size], #[varint], #[tag] or #[bits]",
+        ));
+    }
+    let var: Ident = attr.parse_args()?;
+    match infos.iter().find(|fi| fi.var == var) {
+        None => return Err(Error::new_spanned(&var, format!("unknown field: {}", var))),
+        //the packed integer has no length of its own
+        Some(fi) if get_attr(&fi.field.attrs, ATTR_BITS).is_some() => {
+            return Err(Error::new_spanned(
+                &var,
+                format!("#[len_of] field can't be #[bits]: {}", var),
+            ))
+        }
+        Some(_) => {}
+    }
+    Ok(Some(LenOf { var, count }))
+}
+
+///Group the consecutive #[bits] fields into the smallest integer.
+/// The first field is in the most significant bits, or the least significant bits with lsb_first.
+#[inline]
+fn pack_bits(infos: &mut [FieldInfo], lsb_first: bool) -> syn::Result<()> {
+    let mut i = 0;
+    while i < infos.len() {
+        let widths = infos[i..]
+            .iter()
+            .map_while(|fi| {
+                get_attr(&fi.field.attrs, ATTR_BITS).map(|attr| get_bits(fi.field, attr))
+            })
+            .collect::<syn::Result<Vec<u32>>>()?;
+        if widths.is_empty() {
+            i += 1;
+            continue;
+        }
+        let total: u32 = widths.iter().sum();
+        let size = [8, 16, 32, 64, 128]
+            .iter()
+            .copied()
+            .find(|&size| size >= total)
+            .ok_or_else(|| {
+                Error::new_spanned(
+                    infos[i].field,
+                    format!("the #[bits] fields have {} bits, more than 128", total),
+                )
+            })?;
+        //the group is sent as one integer with the byte order of the first field
+        if let Some(attr) = infos[i + 1..i + widths.len()].iter().find_map(|fi| {
+            get_attr(&fi.field.attrs, ATTR_BE).or_else(|| get_attr(&fi.field.attrs, ATTR_LE))
+        }) {
+            return Err(Error::new_spanned(
+                attr,
+                "#[be]/#[le] of the #[bits] fields must be on the first field of the group",
+            ));
+        }
+        let mut used = 0;
+        for (k, &width) in widths.iter().enumerate() {
+            let shift = if lsb_first { used } else { size - used - width };
+            used += width;
+            infos[i + k].bits = Some(Bits {
+                width,
+                shift,
+                group: (k == 0).then(|| (format_ident!("u{}", size), widths.len())),
+            });
+        }
+        i += widths.len();
+    }
+    Ok(())
+}
+
+///The low n bits
+#[inline]
+fn bits_mask(width: u32) -> syn::LitInt {
+    syn::LitInt::new(&((1_u128 << width) - 1).to_string(), Span::call_site())
+}
+
+///i8 to i128 and isize, the #[bits] fields are two's complement
+#[inline]
+fn is_signed(ty: &Type) -> bool {
+    const SIGNED: [&str; 6] = ["i8", "i16", "i32", "i64", "i128", "isize"];
+    matches!(ty, Type::Path(p) if SIGNED.iter().any(|t| p.path.is_ident(t)))
+}
+
+///n in #[bits(n)]: 1 to 64
+#[inline]
+fn get_bits(f: &Field, attr: &Attribute) -> syn::Result<u32> {
+    let own = [
+        ATTR_SKIP,
+        ATTR_WHEN,
+        ATTR_LEN,
+        ATTR_SIZE,
+        ATTR_VARINT,
+        ATTR_TAG,
+    ];
+    if own.iter().any(|&a| get_attr(&f.attrs, a).is_some()) {
+        return Err(Error::new_spanned(
+            attr,
+            "#[bits] can't be used with #[skip], #[when], #[len], #[size], #[varint] or #[tag]",
+        ));
+    }
+    let n = attr.parse_args::<syn::LitInt>();
+    match n.and_then(|n| n.base10_parse()) {
+        Ok(n) if (1..=64).contains(&n) => Ok(n),
+        _ => Err(Error::new_spanned(
+            attr,
+            "invalid bits attribute, expected: #[bits(1 to 64)]",
+        )),
+    }
+}
+
+///The field name or index: x or 0
+#[inline]
+fn member_name(fi: &FieldInfo) -> String {
+    match &fi.member {
+        Member::Named(id) => id.to_string(),
+        Member::Unnamed(i) => i.index.to_string(),
+    }
+}
+
+///The byte order of the type: BE with #[resend(big)], LE with #[resend(little)]
+#[inline]
+fn type_order(attrs: &[Attribute]) -> syn::Result<Option<&'static str>> {
+    let big = resend_arg(attrs, ARG_BIG);
+    Ok(match (big, resend_arg(attrs, ARG_LITTLE)) {
+        (Some(_), Some(little)) => {
+            return Err(Error::new_spanned(
+                little,
+                "#[resend(big)] and #[resend(little)] can't be used together",
+            ))
+        }
+        (Some(_), None) => Some("BE"),
+        (None, Some(_)) => Some("LE"),
+        (None, None) => None,
+    })
+}
+
+///The byte order of the field: #[be], #[le] or the order of the type.
+/// The fields with #[len], #[varint], #[tag] or the reference type keep their own encoding.
+#[inline]
+fn field_order(f: &Field, type_order: Option<&'static str>) -> syn::Result<Option<Ident>> {
+    let attrs = &f.attrs;
+    let order = match (get_attr(attrs, ATTR_BE), get_attr(attrs, ATTR_LE)) {
+        (Some(_), Some(le)) => {
+            return Err(Error::new_spanned(
+                le,
+                "#[be] and #[le] can't be used together",
+            ))
+        }
+        (Some(be), None) => Some((ATTR_BE, Some(be))),
+        (None, Some(le)) => Some((ATTR_LE, Some(le))),
+        (None, None) => type_order.map(|o| (o, None)),
+    };
+    let (order, explicit) = match order {
+        Some(order) => order,
+        None => return Ok(None),
+    };
+    let own = [ATTR_SKIP, ATTR_LEN, ATTR_VARINT, ATTR_TAG]
+        .iter()
+        .any(|a| get_attr(attrs, a).is_some())
+        || matches!(f.ty, Type::Reference(_));
+    if own {
+        return match explicit {
+            Some(attr) => Err(Error::new_spanned(
+                attr,
+                "#[be] and #[le] can't be used with #[skip], #[len], #[varint], #[tag] or the reference",
+            )),
+            None => Ok(None),
+        };
+    }
+    Ok(Some(Ident::new(&order.to_uppercase(), Span::call_site())))
+}
+
+///self.x for the variable x, self.0 for the variable _0 of tuple struct
+#[inline]
+fn self_member(var: &Ident, is_tuple: bool) -> Member {
+    if is_tuple {
+        if let Some(Ok(i)) = var.to_string().strip_prefix('_').map(str::parse::<u32>) {
+            return Member::Unnamed(Index {
+                index: i,
+                span: var.span(),
+            });
+        }
     }
+    Member::Named(var.clone())
 }
+
+///The lifetime of the borrowed fields: the first lifetime parameter
+#[inline]
+fn get_first_lifetime(ast: &syn::DeriveInput) -> Option<&Lifetime> {
+    ast.generics.lifetimes().next().map(|l| &l.lifetime)
+}
+
+///The type has the reference with the lifetime: &'a [u8], Option<&'a str> etc., not PhantomData<&'a T>
+#[inline]
+fn borrows(ty: &Type, lt: &Lifetime) -> bool {
+    match ty {
+        Type::Reference(r) => r.lifetime.as_ref() == Some(lt) || borrows(&r.elem, lt),
+        Type::Path(p) => p.path.segments.iter().any(|s| match &s.arguments {
+            syn::PathArguments::AngleBracketed(args) if s.ident != "PhantomData" => {
+                args.args.iter().any(|arg| match arg {
+                    syn::GenericArgument::Type(t) => borrows(t, lt),
+                    _ => false,
+                })
+            }
+            _ => false,
+        }),
+        Type::Array(a) => borrows(&a.elem, lt),
+        Type::Slice(a) => borrows(&a.elem, lt),
+        Type::Tuple(t) => t.elems.iter().any(|t| borrows(t, lt)),
+        Type::Paren(p) => borrows(&p.elem, lt),
+        Type::Group(g) => borrows(&g.elem, lt),
+        _ => false,
+    }
+}
+
+///The generics of the impl: T: bound for every type parameter,
+/// or the predicates in #[resend(bound = "T: Trait")] if the bound can't be inferred
+#[inline]
+fn bounded_generics(ast: &syn::DeriveInput, bound: TokenStream2) -> syn::Result<syn::Generics> {
+    let mut generics = ast.generics.clone();
+    let predicates: Vec<WherePredicate> = match get_resend_value(&ast.attrs, ARG_BOUND)? {
+        Some(s) => s
+            .parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?
+            .into_iter()
+            .collect(),
+        None => ast
+            .generics
+            .type_params()
+            .map(|t| {
+                let id = &t.ident;
+                parse_quote! { #id: #bound }
+            })
+            .collect(),
+    };
+    generics.make_where_clause().predicates.extend(predicates);
+    Ok(generics)
+}
+
+///The catch-all variant with #[resend(other)], the tag is its first field
 #[inline]
-fn get_lifetime(ast: &syn::DeriveInput) -> Vec<&GenericParam> {
-    let mut generics = Vec::new();
-    for g in &ast.generics.params {
-        generics.push(g);
+fn get_other_variant(data: &syn::DataEnum) -> syn::Result<Option<&syn::Variant>> {
+    let mut others = data
+        .variants
+        .iter()
+        .filter(|va| has_resend_arg(&va.attrs, ARG_OTHER));
+    let other = others.next();
+    if let Some(va) = others.next() {
+        return Err(Error::new_spanned(
+            &va.ident,
+            "only one variant can be #[resend(other)]",
+        ));
+    }
+    if let Some(va) = other {
+        if va.fields.is_empty() {
+            return Err(Error::new_spanned(
+                &va.ident,
+                format!(
+                    "#[resend(other)] variant needs the tag field: {}(tag_type)",
+                    va.ident
+                ),
+            ));
+        }
     }
+    Ok(other)
+}
 
-    generics
+///The fields of the catch-all variant after the tag, and the trailing Vec<u8> without #[len]/#[size]:
+/// it's the rest of the bytes (#[size] of the enum, frame etc.), sent without Length.
+#[inline]
+fn other_fields<'a, 'b>(
+    infos: &'b [FieldInfo<'a>],
+) -> (&'b [FieldInfo<'a>], Option<&'b FieldInfo<'a>>) {
+    let fields = &infos[1..];
+    match fields.split_last() {
+        Some((last, rest))
+            if last.field.ty == parse_quote! { Vec<u8> }
+                && get_attr(&last.field.attrs, ATTR_LEN).is_none()
+                && get_attr(&last.field.attrs, ATTR_SIZE).is_none() =>
+        {
+            (rest, Some(last))
+        }
+        _ => (fields, None),
+    }
+}
+
+///Check the flag in #[resend(...)], for example: #[resend(other)]
+#[inline]
+fn has_resend_arg(attrs: &[Attribute], arg: &str) -> bool {
+    resend_arg(attrs, arg).is_some()
+}
+
+///The flag in #[resend(...)] for the error span
+#[inline]
+fn resend_arg(attrs: &[Attribute], arg: &str) -> Option<syn::Path> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident(ATTR_RESEND))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(l)) => Some(l.nested),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|m| match m {
+            syn::NestedMeta::Meta(syn::Meta::Path(p)) if p.is_ident(arg) => Some(p),
+            _ => None,
+        })
+}
+
+///The string value in #[resend(...)], for example: #[resend(bound = "T: Trait")]
+#[inline]
+fn get_resend_value(attrs: &[Attribute], arg: &str) -> syn::Result<Option<syn::LitStr>> {
+    let value = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident(ATTR_RESEND))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(l)) => Some(l.nested),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|m| match m {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) if nv.path.is_ident(arg) => {
+                Some(nv.lit)
+            }
+            _ => None,
+        });
+    match value {
+        Some(syn::Lit::Str(s)) => Ok(Some(s)),
+        Some(lit) => Err(Error::new_spanned(
+            lit,
+            format!("#[resend({} = \"...\")] expected", arg),
+        )),
+        None => Ok(None),
+    }
 }
 
 #[inline]
@@ -365,55 +1591,103 @@ fn get_attr<'a>(attrs: &'a [Attribute], value: &str) -> Option<&'a Attribute> {
     attrs.iter().find(|&attr| attr.path.is_ident(value))
 }
 
-enum AttrLen {
-    Ident(syn::Ident),
-    LitInt(syn::LitInt),
-    None,
+///The value of #[len(expr)] or #[size(expr)] in parentheses, the expression is a constant
+/// or computed from the fields before it: #[len(cb_name - 2)], #[len((flags >> 4) & 0xF)].
+/// value: the field variable when it's sent (self.x)
+#[inline]
+fn attr_len_value(
+    attr: &Attribute,
+    infos: &[FieldInfo],
+    value: impl Fn(&Ident) -> TokenStream2,
+) -> syn::Result<TokenStream2> {
+    let exp = self_expr(attr.parse_args()?, infos, value);
+    Ok(quote! { (#exp) })
 }
+///The integer type in #[repr(...)], for example: #[repr(C, u16)]. u8 without #[repr]
 #[inline]
-fn get_attr_len(attr: &Attribute) -> AttrLen {
-    if attr.path.is_ident(ATTR_LEN) {
-        if let Ok(syn::Meta::List(l)) = attr.parse_meta() {
-            if let Some(v) = l.nested.first() {
-                match v {
-                    syn::NestedMeta::Meta(m) => {
-                        return AttrLen::Ident(m.path().get_ident().unwrap().clone());
-                    }
-                    syn::NestedMeta::Lit(syn::Lit::Int(i)) => {
-                        return AttrLen::LitInt(i.clone());
-                    }
-                    _ => (),
+fn get_repr_int(attrs: &[Attribute]) -> syn::Result<Ident> {
+    let mut reprs = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .peekable();
+    let first = match reprs.peek() {
+        Some(&attr) => attr,
+        None => return Ok(Ident::new("u8", Span::call_site())),
+    };
+    for attr in reprs {
+        let args =
+            attr.parse_args_with(Punctuated::<syn::NestedMeta, Token![,]>::parse_terminated)?;
+        for m in &args {
+            if let syn::NestedMeta::Meta(syn::Meta::Path(p)) = m {
+                if let Some(v) = p.get_ident().filter(|v| INT_TYPES.iter().any(|t| v == t)) {
+                    return Ok(v.clone());
                 }
             }
         }
     }
-    AttrLen::None
+    Err(Error::new_spanned(
+        first,
+        "the tag type is unknown, add the integer type to #[repr], for example: #[repr(C, u8)]",
+    ))
 }
+///The tag expression and the check flag in #[tag(header.kind, check)]
 #[inline]
-fn get_attr_meta(attr: &Attribute) -> Option<Ident> {
-    if let Ok(syn::Meta::List(l)) = attr.parse_meta() {
-        if let Some(syn::NestedMeta::Meta(m)) = l.nested.first() {
-            if let Some(v) = m.path().get_ident() {
-                return Some(v.clone());
-            }
+fn get_tag_args(attr: &Attribute) -> syn::Result<(Expr, bool)> {
+    let args = attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+    let mut args = args.into_iter();
+    let tag = args
+        .next()
+        .ok_or_else(|| Error::new_spanned(attr, "tag expression expected: #[tag(field.kind)]"))?;
+    let check = match args.next() {
+        Some(Expr::Path(p)) if p.path.is_ident(ARG_CHECK) => true,
+        None => false,
+        Some(arg) => {
+            return Err(Error::new_spanned(
+                arg,
+                "invalid tag attribute, expected: #[tag(field.kind, check)]",
+            ))
         }
+    };
+    if let Some(arg) = args.next() {
+        return Err(Error::new_spanned(
+            arg,
+            "invalid tag attribute, expected: #[tag(field.kind, check)]",
+        ));
     }
-    None
+    Ok((tag, check))
 }
 
+///Replace the field variables in the expression with their values when it's sent.
+/// header.kind => self.header.kind
 #[inline]
-fn get_when_args(attr: &Attribute) -> Expr {
-    // attr.parse_meta return error here, so we use to_string
-    let mut s = attr.tokens.to_string();
-    if s.len() > 2 {
-        //must be (x) at least
-        let first = s.remove(0);
-        let last = s.pop().unwrap();
-        if first == '(' && last == ')' {
-            return parse_str::<Expr>(&s).unwrap();
+fn self_expr(mut exp: Expr, infos: &[FieldInfo], value: impl Fn(&Ident) -> TokenStream2) -> Expr {
+    struct Fields<'a, F> {
+        infos: &'a [FieldInfo<'a>],
+        value: F,
+    }
+
+    impl<F: Fn(&Ident) -> TokenStream2> VisitMut for Fields<'_, F> {
+        fn visit_expr_mut(&mut self, exp: &mut Expr) {
+            if let Expr::Path(p) = exp {
+                if let Some(id) = p.path.get_ident() {
+                    if self.infos.iter().any(|fi| &fi.var == id) {
+                        *exp = syn::parse2((self.value)(id)).unwrap();
+                        return;
+                    }
+                }
+            }
+            syn::visit_mut::visit_expr_mut(self, exp);
         }
     }
-    panic!("Inlvalid args: {}", s);
+
+    Fields { infos, value }.visit_expr_mut(&mut exp);
+    exp
+}
+
+///The condition in #[when(header.flag)]
+#[inline]
+fn get_when_args(attr: &Attribute) -> syn::Result<Expr> {
+    attr.parse_args()
 }
 
 #[inline]
@@ -426,15 +1700,17 @@ fn is_option(p: &TypePath) -> bool {
         _ => false,
     }
 }
+
+///T of Option<T>, the type of #[when] field
 #[inline]
-fn parse_int(s: &str) -> Result<u32, ParseIntError> {
-    if let Some(stripped) = s.strip_prefix("0x") {
-        u32::from_str_radix(stripped, 16)
-    } else if let Some(stripped) = s.strip_prefix("0b") {
-        u32::from_str_radix(stripped, 2)
-    } else if let Some(stripped) = s.strip_prefix("0o") {
-        u32::from_str_radix(stripped, 8)
-    } else {
-        s.parse::<u32>()
+fn option_arg(ty: &Type) -> syn::Result<&Type> {
+    if let Type::Path(p) = ty {
+        let args = p.path.segments.last().map(|s| &s.arguments);
+        if let (true, Some(syn::PathArguments::AngleBracketed(args))) = (is_option(p), args) {
+            if let Some(syn::GenericArgument::Type(ty)) = args.args.first() {
+                return Ok(ty);
+            }
+        }
     }
+    Err(Error::new_spanned(ty, "#[when] field must be Option<T>"))
 }
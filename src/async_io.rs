@@ -0,0 +1,844 @@
+//! AsyncSender, AsyncReceiver, AsyncSnd, AsyncRcv traits for tokio streams ("tokio" feature).
+//!
+//! Sending reuses the Sendable implementations: the data is serialized to a buffer and written at once.
+//! Receiving reads from the stream directly, #[derive(Rcv)] implements AsyncReceivable too.
+//!
+//! The traits use `async fn`, so the futures aren't declared Send: they work with any AsyncRead/AsyncWrite.
+//! They're Send with the concrete types (tokio::spawn with TcpStream etc.), but generic code can't require
+//! `R: AsyncReceiver` futures to be Send.
+
+#![allow(async_fn_in_trait)]
+
+use std::collections::{BTreeMap, HashMap, LinkedList, VecDeque};
+use std::num::{
+    NonZeroI16, NonZeroI32, NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroUsize,
+};
+use std::{borrow::Cow, ffi::CString, hash::Hash, ops::Range, path::PathBuf, time::Duration};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::bounded::Bounded;
+use crate::endian::leb128::Leb128Decoder;
+use crate::endian::{
+    Ascii, ByteOrder, ByteOrdered, Length, OrderedRef, SLeb128, ULeb128, UTF16Char, ZigZag, BE, LE,
+    UTF16, VLQ,
+};
+use crate::strict::Strict;
+use crate::tracked::Tracked;
+use crate::{FromReader, Result, Sendable};
+
+///Async version of Sender
+pub trait AsyncSender {
+    async fn snd_all(&mut self, buf: &[u8]) -> Result<()>;
+
+    async fn flush(&mut self) -> Result<()>;
+
+    ///Byte order for numbers, Length etc. The default is from the "little"/"big" feature.
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        ByteOrder::DEFAULT
+    }
+}
+
+///Async version of Receiver
+pub trait AsyncReceiver {
+    async fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()>;
+
+    #[inline]
+    async fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        let mut vec = vec![0; len];
+        self.rcv_all(&mut vec).await?;
+        Ok(vec)
+    }
+
+    ///Receive all the bytes left: the rest of #[size] (bounded::Bounded), the stream until the end.
+    /// It only returns at the end of the stream, it waits forever on an open socket: bound it with #[size].
+    #[inline]
+    async fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        let mut buf = [0];
+        loop {
+            match self.rcv_all(&mut buf).await {
+                Ok(()) => vec.push(buf[0]),
+                Err(e) if crate::error::is_eof(&e) => return Ok(vec),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    ///Byte order for numbers, Length etc. The default is from the "little"/"big" feature.
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        ByteOrder::DEFAULT
+    }
+
+    ///The byte offset of the next read for the error context, None if it's not tracked.
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        None
+    }
+
+    ///Reject the non-canonical data, the default is from the "strict" feature.
+    #[inline]
+    fn is_strict(&self) -> bool {
+        cfg!(feature = "strict")
+    }
+}
+
+///Implemented for all Sendable types.
+pub trait AsyncSendable {
+    async fn snd_to<S>(&self, writer: &mut S) -> Result<()>
+    where
+        S: AsyncSender;
+}
+
+///Async version of Receivable, #[derive(Rcv)] implements it with the "tokio" feature.
+pub trait AsyncReceivable: Sized {
+    async fn rcv_from<R>(reader: &mut R) -> Result<Self>
+    where
+        R: AsyncReceiver;
+}
+
+///Send Trait for AsyncSender
+pub trait AsyncSnd {
+    async fn snd<T>(&mut self, v: T) -> Result<()>
+    where
+        T: AsyncSendable;
+}
+
+///Receive Trait for AsyncReceiver
+pub trait AsyncRcv {
+    async fn rcv<T>(&mut self) -> Result<T>
+    where
+        T: AsyncReceivable;
+}
+
+///Async version of FromReader for the #[len] attribute
+pub trait AsyncFromReader: Sized {
+    async fn from_reader<R: AsyncReceiver>(reader: &mut R, len: usize) -> Result<Self>;
+}
+
+///Async version of FromTag for the #[tag] attribute
+pub trait AsyncFromTag: Sized {
+    type Tag;
+
+    async fn from_tag<R: AsyncReceiver>(reader: &mut R, tag: Self::Tag) -> Result<Self>;
+}
+
+impl<S: AsyncSender> AsyncSnd for S {
+    #[inline]
+    async fn snd<T: AsyncSendable>(&mut self, v: T) -> Result<()> {
+        v.snd_to(self).await
+    }
+}
+
+impl<R: AsyncReceiver> AsyncRcv for R {
+    #[inline]
+    async fn rcv<T: AsyncReceivable>(&mut self) -> Result<T> {
+        T::rcv_from(self).await
+    }
+}
+
+//impl AsyncSender for all AsyncWrite implementors
+impl<W: AsyncWrite + Unpin + ?Sized> AsyncSender for W {
+    #[inline]
+    async fn snd_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.write_all(buf).await?;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        AsyncWriteExt::flush(self).await?;
+        Ok(())
+    }
+}
+
+//impl AsyncReceiver for all AsyncRead implementors
+impl<R: AsyncRead + Unpin + ?Sized> AsyncReceiver for R {
+    #[inline]
+    async fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.read_exact(buf).await?;
+        Ok(())
+    }
+
+    #[inline]
+    async fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        self.read_to_end(&mut vec).await?;
+        Ok(vec)
+    }
+}
+
+impl<T: AsyncSender> AsyncSender for ByteOrdered<T> {
+    #[inline]
+    async fn snd_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.inner.snd_all(buf).await
+    }
+
+    #[inline]
+    async fn flush(&mut self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.order
+    }
+}
+
+impl<T: AsyncReceiver> AsyncReceiver for ByteOrdered<T> {
+    #[inline]
+    async fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.inner.rcv_all(buf).await
+    }
+
+    #[inline]
+    async fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        self.inner.rcv_bytes(len).await
+    }
+
+    #[inline]
+    async fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        self.inner.rcv_rest().await
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.order
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        self.inner.position()
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        self.inner.is_strict()
+    }
+}
+
+impl<T: AsyncReceiver + ?Sized> AsyncReceiver for OrderedRef<'_, T> {
+    #[inline]
+    async fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.inner.rcv_all(buf).await
+    }
+
+    #[inline]
+    async fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        self.inner.rcv_bytes(len).await
+    }
+
+    #[inline]
+    async fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        self.inner.rcv_rest().await
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.order
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        self.inner.position()
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        self.inner.is_strict()
+    }
+}
+
+impl<R: AsyncReceiver + ?Sized> AsyncReceiver for Bounded<'_, R> {
+    #[inline]
+    async fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.take(buf.len())?;
+        self.get_mut().rcv_all(buf).await
+    }
+
+    #[inline]
+    async fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        self.take(len)?;
+        self.get_mut().rcv_bytes(len).await
+    }
+
+    #[inline]
+    async fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        self.rcv_bytes(self.remaining()).await
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.get_ref().byte_order()
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        self.get_ref().position()
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        self.get_ref().is_strict()
+    }
+}
+
+impl<R: AsyncReceiver> AsyncReceiver for Tracked<R> {
+    #[inline]
+    async fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.get_mut().rcv_all(buf).await?;
+        self.advance(buf.len());
+        Ok(())
+    }
+
+    #[inline]
+    async fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        let vec = self.get_mut().rcv_bytes(len).await?;
+        self.advance(len);
+        Ok(vec)
+    }
+
+    #[inline]
+    async fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        let vec = self.get_mut().rcv_rest().await?;
+        self.advance(vec.len());
+        Ok(vec)
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.get_ref().byte_order()
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        Some(self.offset())
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        self.get_ref().is_strict()
+    }
+}
+
+impl<R: AsyncReceiver> AsyncReceiver for Strict<R> {
+    #[inline]
+    async fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.get_mut().rcv_all(buf).await
+    }
+
+    #[inline]
+    async fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        self.get_mut().rcv_bytes(len).await
+    }
+
+    #[inline]
+    async fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        self.get_mut().rcv_rest().await
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.get_ref().byte_order()
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        self.get_ref().position()
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        true
+    }
+}
+
+impl<R: AsyncReceiver + ?Sized> Bounded<'_, R> {
+    ///Async version of skip_rest
+    pub async fn skip_rest_async(&mut self) -> Result<()> {
+        let mut buf = [0; 256];
+        while self.remaining() > 0 {
+            let n = self.remaining().min(buf.len());
+            self.rcv_all(&mut buf[..n]).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Sendable + ?Sized> AsyncSendable for T {
+    #[inline]
+    async fn snd_to<S: AsyncSender>(&self, writer: &mut S) -> Result<()> {
+        let mut buf = ByteOrdered::new(Vec::new(), writer.byte_order());
+        Sendable::snd_to(self, &mut buf)?;
+        writer.snd_all(&buf.inner).await
+    }
+}
+
+//Types with fixed size: read the bytes then reuse the Receivable implementation, strict if the reader is
+macro_rules! rcv_fixed {
+    ($($t:ty: $n:expr),+ $(,)?) => {
+        $(
+            impl AsyncReceivable for $t {
+                #[inline]
+                async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+                    let mut buf = [0; $n];
+                    reader.rcv_all(&mut buf).await?;
+                    let mut bytes = ByteOrdered::new(&buf[..], reader.byte_order());
+                    if reader.is_strict() {
+                        <$t as crate::Receivable>::rcv_from(&mut Strict::new(bytes))
+                    } else {
+                        <$t as crate::Receivable>::rcv_from(&mut bytes)
+                    }
+                }
+            }
+        )+
+    };
+}
+
+rcv_fixed!(
+    u8: 1,
+    i8: 1,
+    usize: 8,
+    isize: 8,
+    char: 4,
+    NonZeroU16: 2,
+    NonZeroI16: 2,
+    NonZeroU32: 4,
+    NonZeroI32: 4,
+    NonZeroU64: 8,
+    NonZeroUsize: 8,
+    NonZeroU128: 16,
+);
+
+rcv_fixed!(
+    u16: 2,
+    i16: 2,
+    u32: 4,
+    i32: 4,
+    f32: 4,
+    u64: 8,
+    i64: 8,
+    f64: 8,
+    u128: 16,
+    i128: 16,
+    Duration: 12,
+);
+
+//Any AsyncReceivable, received with the byte order like Receivable for LE<T> and BE<T>
+impl<T: AsyncReceivable> AsyncReceivable for LE<T> {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let mut reader = OrderedRef::new(reader, ByteOrder::Little);
+        Ok(LE(T::rcv_from(&mut reader).await?))
+    }
+}
+
+impl<T: AsyncReceivable> AsyncReceivable for BE<T> {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let mut reader = OrderedRef::new(reader, ByteOrder::Big);
+        Ok(BE(T::rcv_from(&mut reader).await?))
+    }
+}
+
+impl AsyncReceivable for bool {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let b = u8::rcv_from(reader).await?;
+        crate::endian::bool_from_byte(b, reader.is_strict())
+    }
+}
+
+impl AsyncReceivable for Length {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        #[cfg(not(any(feature = "len_vlq", feature = "len_16", feature = "len_leb128")))]
+        let len = u32::rcv_from(reader).await? as usize;
+
+        #[cfg(feature = "len_16")]
+        let len = u16::rcv_from(reader).await? as usize;
+
+        #[cfg(feature = "len_vlq")]
+        let len = VLQ::rcv_from(reader).await?.0;
+
+        #[cfg(feature = "len_leb128")]
+        let len = Length::from_leb128(ULeb128::rcv_from(reader).await?)?;
+
+        Ok(Length(len))
+    }
+}
+
+impl AsyncReceivable for VLQ {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let strict = reader.is_strict();
+        let mut v = 0;
+        let mut n = 0;
+        loop {
+            let b = u8::rcv_from(reader).await?;
+            v = VLQ::push_byte(v, b, n, strict)?;
+            n += 1;
+
+            if (b & 128) == 0 {
+                break;
+            }
+        }
+        Ok(VLQ(v))
+    }
+}
+
+impl AsyncReceivable for ULeb128 {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let strict = reader.is_strict();
+        let mut decoder = Leb128Decoder::default();
+        while !decoder.push(u8::rcv_from(reader).await?, false, strict)? {}
+        Ok(decoder.unsigned())
+    }
+}
+
+impl AsyncReceivable for SLeb128 {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let strict = reader.is_strict();
+        let mut decoder = Leb128Decoder::default();
+        while !decoder.push(u8::rcv_from(reader).await?, true, strict)? {}
+        Ok(decoder.signed())
+    }
+}
+
+macro_rules! rcv_zigzag {
+    ($($t:ty),+) => {
+        $(
+            impl AsyncReceivable for ZigZag<$t> {
+                #[inline]
+                async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+                    Self::from_leb128(ULeb128::rcv_from(reader).await?)
+                }
+            }
+        )+
+    };
+}
+
+rcv_zigzag!(i16, i32, i64);
+
+impl AsyncReceivable for CString {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let mut vec = Vec::new();
+        loop {
+            let b = u8::rcv_from(reader).await?;
+            vec.push(b);
+            if b == 0 {
+                break;
+            }
+        }
+        Ok(unsafe { CString::from_vec_with_nul_unchecked(vec) })
+    }
+}
+
+impl AsyncReceivable for String {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let len = *Length::rcv_from(reader).await?;
+        let buffer = reader.rcv_bytes(len).await?;
+        let s = std::str::from_utf8(&buffer)?;
+        Ok(s.to_string())
+    }
+}
+
+impl AsyncReceivable for Ascii {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let len = *Length::rcv_from(reader).await?;
+        let buf = reader.rcv_bytes(len).await?;
+        Ascii::from_bytes(&buf, reader.is_strict())
+    }
+}
+
+impl AsyncReceivable for UTF16Char {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        rcv_utf16_char(reader, reader.byte_order()).await
+    }
+}
+
+impl AsyncReceivable for UTF16 {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        rcv_utf16(reader, reader.byte_order()).await
+    }
+}
+
+#[inline]
+async fn rcv_utf16_char<R: AsyncReceiver>(reader: &mut R, order: ByteOrder) -> Result<UTF16Char> {
+    let mut buf = [0; 4];
+    reader.rcv_all(&mut buf[..2]).await?;
+    let high = match order {
+        ByteOrder::Little => u16::from_le_bytes([buf[0], buf[1]]),
+        ByteOrder::Big => u16::from_be_bytes([buf[0], buf[1]]),
+    };
+    let n = if (0xD800..=0xDBFF).contains(&high) {
+        //It's 4 byte long
+        reader.rcv_all(&mut buf[2..]).await?;
+        4
+    } else {
+        2
+    };
+    <UTF16Char as crate::Receivable>::rcv_from(&mut ByteOrdered::new(&buf[..n], order))
+}
+
+//The Length header follows the reader, the chars follow "order"
+#[inline]
+async fn rcv_utf16<R: AsyncReceiver>(reader: &mut R, order: ByteOrder) -> Result<UTF16> {
+    let len = *Length::rcv_from(reader).await?;
+    let buf = reader.rcv_bytes(len).await?;
+    let mut chars = ByteOrdered::new(&buf[..], order);
+    let mut s = String::with_capacity(len / 2);
+    while !chars.inner.is_empty() {
+        let c = <UTF16Char as crate::Receivable>::rcv_from(&mut chars)?;
+        s.push(*c);
+    }
+    Ok(UTF16(s))
+}
+
+impl<T: AsyncReceivable, const N: usize> AsyncReceivable for [T; N] {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let mut v = Vec::with_capacity(N);
+        for _ in 0..N {
+            v.push(T::rcv_from(reader).await?);
+        }
+        Self::try_from(v).map_err(|_| crate::error::Error::Other("convert vec to array error"))
+    }
+}
+
+impl<T: AsyncReceivable> AsyncReceivable for Vec<T> {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let len = *Length::rcv_from(reader).await?;
+        let mut v = Vec::with_capacity(len);
+        for _ in 0..len {
+            v.push(T::rcv_from(reader).await?);
+        }
+        Ok(v)
+    }
+}
+
+impl<T: AsyncReceivable> AsyncReceivable for VecDeque<T> {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let len = *Length::rcv_from(reader).await?;
+        let mut v = VecDeque::with_capacity(len);
+        for _ in 0..len {
+            v.push_back(T::rcv_from(reader).await?);
+        }
+        Ok(v)
+    }
+}
+
+impl<T: AsyncReceivable> AsyncReceivable for LinkedList<T> {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let len = *Length::rcv_from(reader).await?;
+        let mut v = LinkedList::new();
+        for _ in 0..len {
+            v.push_back(T::rcv_from(reader).await?);
+        }
+        Ok(v)
+    }
+}
+
+impl<T: AsyncReceivable> AsyncReceivable for Option<T> {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        if bool::rcv_from(reader).await? {
+            Ok(Some(T::rcv_from(reader).await?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<K, V> AsyncReceivable for HashMap<K, V>
+where
+    K: AsyncReceivable + Eq + Hash,
+    V: AsyncReceivable,
+{
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let len = *Length::rcv_from(reader).await?;
+        let mut kv = HashMap::with_capacity(len);
+        for _ in 0..len {
+            let k = K::rcv_from(reader).await?;
+            let v = V::rcv_from(reader).await?;
+            kv.insert(k, v);
+        }
+        Ok(kv)
+    }
+}
+
+impl<K, V> AsyncReceivable for BTreeMap<K, V>
+where
+    K: AsyncReceivable + Ord,
+    V: AsyncReceivable,
+{
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let len = *Length::rcv_from(reader).await?;
+        let mut kv = BTreeMap::new();
+        for _ in 0..len {
+            let k = K::rcv_from(reader).await?;
+            let v = V::rcv_from(reader).await?;
+            kv.insert(k, v);
+        }
+        Ok(kv)
+    }
+}
+
+impl<T: AsyncReceivable> AsyncReceivable for Box<T> {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        Ok(Box::new(T::rcv_from(reader).await?))
+    }
+}
+
+impl<T: AsyncReceivable + Clone> AsyncReceivable for Cow<'_, T> {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        Ok(Cow::Owned(T::rcv_from(reader).await?))
+    }
+}
+
+impl<Idx: AsyncReceivable> AsyncReceivable for Range<Idx> {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        let start = Idx::rcv_from(reader).await?;
+        let end = Idx::rcv_from(reader).await?;
+        Ok(Range { start, end })
+    }
+}
+
+impl AsyncReceivable for PathBuf {
+    #[inline]
+    async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+        Ok(PathBuf::from(String::rcv_from(reader).await?))
+    }
+}
+
+macro_rules! rcv_tuple {
+    ($($name:ident), +) => {
+        impl<$($name: AsyncReceivable),+> AsyncReceivable for ($($name,)+) {
+            #[inline]
+            async fn rcv_from<R: AsyncReceiver>(reader: &mut R) -> Result<Self> {
+                Ok(($($name::rcv_from(reader).await?,)+))
+            }
+        }
+    };
+}
+
+rcv_tuple!(A, B);
+rcv_tuple!(A, B, C);
+rcv_tuple!(A, B, C, D);
+rcv_tuple!(A, B, C, D, E);
+rcv_tuple!(A, B, C, D, E, F);
+rcv_tuple!(A, B, C, D, E, F, G);
+rcv_tuple!(A, B, C, D, E, F, G, H);
+rcv_tuple!(A, B, C, D, E, F, G, H, I);
+rcv_tuple!(A, B, C, D, E, F, G, H, I, J);
+rcv_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+rcv_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+//String, Ascii and UTF16 with #[len]: read the bytes then reuse the FromReader implementation, strict if the reader is
+macro_rules! from_reader_bytes {
+    ($($t:ty),+) => {
+        $(
+            impl AsyncFromReader for $t {
+                #[inline]
+                async fn from_reader<R: AsyncReceiver>(reader: &mut R, len: usize) -> Result<Self> {
+                    let buf = reader.rcv_bytes(len).await?;
+                    let mut bytes = ByteOrdered::new(&buf[..], reader.byte_order());
+                    if reader.is_strict() {
+                        <$t as FromReader>::from_reader(&mut Strict::new(bytes), len)
+                    } else {
+                        <$t as FromReader>::from_reader(&mut bytes, len)
+                    }
+                }
+            }
+        )+
+    };
+}
+
+from_reader_bytes!(String, Ascii, UTF16, ZigZag<i16>, ZigZag<i32>, ZigZag<i64>);
+
+impl<T: AsyncReceivable> AsyncFromReader for Vec<T> {
+    #[inline]
+    async fn from_reader<R: AsyncReceiver>(reader: &mut R, len: usize) -> Result<Self> {
+        let mut v = Vec::with_capacity(len);
+        for _ in 0..len {
+            v.push(T::rcv_from(reader).await?);
+        }
+        Ok(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsyncRcv, AsyncSnd};
+    use crate::endian::{Ascii, ByteOrdered, SLeb128, ULeb128, BE, LE, UTF16, VLQ};
+
+    #[tokio::test]
+    async fn test_duplex() -> crate::Result<()> {
+        let (mut client, mut server) = tokio::io::duplex(64);
+
+        client.snd(-8_i8).await?;
+        client.snd(0xFFABCDEF_u32).await?;
+        client.snd("Test").await?;
+        client.snd(UTF16("utf16𝌆".to_string())).await?;
+        client.snd(VLQ(16384)).await?;
+        client.snd((ULeb128(624485), SLeb128(-123456))).await?;
+        client.snd(Some(vec![1_u16, 2])).await?;
+        client.snd(Ascii("ascii".to_string())).await?;
+
+        let v: i8 = server.rcv().await?;
+        assert_eq!(v, -8);
+        let v: u32 = server.rcv().await?;
+        assert_eq!(v, 0xFFABCDEF);
+        let v: String = server.rcv().await?;
+        assert_eq!(v, "Test");
+        let v: UTF16 = server.rcv().await?;
+        assert_eq!(*v, "utf16𝌆");
+        let v: VLQ = server.rcv().await?;
+        assert_eq!(*v, 16384);
+        let v: (ULeb128, SLeb128) = server.rcv().await?;
+        assert_eq!(v, (ULeb128(624485), SLeb128(-123456)));
+        let v: Option<Vec<u16>> = server.rcv().await?;
+        assert_eq!(v, Some(vec![1, 2]));
+        let v: Ascii = server.rcv().await?;
+        assert_eq!(*v, "ascii");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_duplex_byte_order() -> crate::Result<()> {
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = ByteOrdered::big(client);
+        let mut server = ByteOrdered::big(server);
+
+        client.snd(0x0102_u16).await?;
+        client.snd(LE(0x0304_u16)).await?;
+        client.snd((BE(5_u32), LE(UTF16("le".to_string())))).await?;
+
+        let mut b = [0_u8; 4];
+        tokio::io::AsyncReadExt::read_exact(server.get_mut(), &mut b).await?;
+        assert_eq!(b, [1, 2, 4, 3]);
+
+        let v: (BE<u32>, LE<UTF16>) = server.rcv().await?;
+        assert_eq!(*v.0, 5);
+        assert_eq!(**v.1, "le");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+//! RefReceiver, RefReceivable, RcvRef traits for zero-copy deserialization from byte slices.
+//!
+//! &'a str and &'a [u8] are borrowed from the buffer without allocation,
+//! #[derive(Rcv)] implements RefReceivable for structs with a lifetime parameter.
+//! All the Receivable types are RefReceivable too.
+
+use crate::endian::{ByteOrdered, Length};
+use crate::{FromReader, Receivable, Receiver, Result};
+
+///Receiver which can lend the bytes of the underlying buffer, implemented for &'a [u8]
+pub trait RefReceiver<'a>: Receiver {
+    fn rcv_slice(&mut self, len: usize) -> Result<&'a [u8]>;
+}
+
+///Impl RefReceivable if the data borrow from the buffer when they're deserialized.
+pub trait RefReceivable<'a>: Sized {
+    fn rcv_ref_from<R>(reader: &mut R) -> Result<Self>
+    where
+        R: RefReceiver<'a>;
+}
+
+///Receive Trait for RefReceiver
+pub trait RcvRef<'a> {
+    fn rcv_ref<T>(&mut self) -> Result<T>
+    where
+        T: RefReceivable<'a>;
+}
+
+///Borrowed version of FromReader for the #[len] attribute
+pub trait RefFromReader<'a>: Sized {
+    fn ref_from_reader<R: RefReceiver<'a>>(reader: &mut R, len: usize) -> Result<Self>;
+}
+
+impl<'a, R: RefReceiver<'a>> RcvRef<'a> for R {
+    #[inline]
+    fn rcv_ref<T: RefReceivable<'a>>(&mut self) -> Result<T> {
+        T::rcv_ref_from(self)
+    }
+}
+
+impl<'a> RefReceiver<'a> for &'a [u8] {
+    #[inline]
+    fn rcv_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.len() {
+            return Err(crate::error::eof());
+        }
+        let (data, rest) = self.split_at(len);
+        *self = rest;
+        Ok(data)
+    }
+}
+
+impl<'a, T: RefReceiver<'a>> RefReceiver<'a> for ByteOrdered<T> {
+    #[inline]
+    fn rcv_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        self.inner.rcv_slice(len)
+    }
+}
+
+impl<'a, T: Receivable> RefReceivable<'a> for T {
+    #[inline]
+    fn rcv_ref_from<R: RefReceiver<'a>>(reader: &mut R) -> Result<Self> {
+        T::rcv_from(reader)
+    }
+}
+
+impl<'a> RefReceivable<'a> for &'a [u8] {
+    #[inline]
+    fn rcv_ref_from<R: RefReceiver<'a>>(reader: &mut R) -> Result<Self> {
+        let len = *Length::rcv_from(reader)?;
+        reader.rcv_slice(len)
+    }
+}
+
+impl<'a> RefReceivable<'a> for &'a str {
+    #[inline]
+    fn rcv_ref_from<R: RefReceiver<'a>>(reader: &mut R) -> Result<Self> {
+        let b = <&[u8]>::rcv_ref_from(reader)?;
+        Ok(core::str::from_utf8(b)?)
+    }
+}
+
+impl<'a, T: FromReader> RefFromReader<'a> for T {
+    #[inline]
+    fn ref_from_reader<R: RefReceiver<'a>>(reader: &mut R, len: usize) -> Result<Self> {
+        T::from_reader(reader, len)
+    }
+}
+
+impl<'a> RefFromReader<'a> for &'a [u8] {
+    #[inline]
+    fn ref_from_reader<R: RefReceiver<'a>>(reader: &mut R, len: usize) -> Result<Self> {
+        reader.rcv_slice(len)
+    }
+}
+
+//extra '\0' are removed like String
+impl<'a> RefFromReader<'a> for &'a str {
+    #[inline]
+    fn ref_from_reader<R: RefReceiver<'a>>(reader: &mut R, len: usize) -> Result<Self> {
+        let b = reader.rcv_slice(len)?;
+        Ok(core::str::from_utf8(b)?.trim_end_matches('\0'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::ByteOrder;
+    use crate::{Sender, Snd};
+
+    #[test]
+    fn test_borrowed() -> Result<()> {
+        let mut vec = Vec::new();
+        vec.snd("hello")?;
+        vec.snd(&[1_u8, 2, 3][..])?;
+        vec.snd(7_u16)?;
+        vec.snd_all(b"ab\0\0")?;
+
+        let mut buf = &vec[..];
+        let s: &str = buf.rcv_ref()?;
+        assert_eq!(s, "hello");
+        //borrowed from vec
+        assert!(vec.as_ptr_range().contains(&s.as_ptr()));
+        let b: &[u8] = buf.rcv_ref()?;
+        assert_eq!(b, [1, 2, 3]);
+        assert_eq!(buf.rcv_ref::<u16>()?, 7);
+        assert_eq!(<&str>::ref_from_reader(&mut buf, 4)?, "ab");
+        assert!(buf.is_empty());
+        assert!(buf.rcv_slice(1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_borrowed_order() -> Result<()> {
+        let mut vec = ByteOrdered::big(Vec::new());
+        vec.snd(1_u32)?;
+        vec.snd("big")?;
+        let v = vec.into_inner();
+        assert_eq!(v[..4], [0, 0, 0, 1]);
+
+        let mut buf = ByteOrdered::new(&v[..], ByteOrder::Big);
+        assert_eq!(buf.rcv_ref::<u32>()?, 1);
+        assert_eq!(buf.rcv_ref::<&str>()?, "big");
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Bounded receiver for the data with known size, for example: the next N bytes are a struct.
+//!
+//! The #[size(field_or_const)] attribute receives the field with Bounded and sends it with snd_sized.
+
+use alloc::vec::Vec;
+
+use crate::borrow::RefReceiver;
+use crate::endian::{ByteOrder, ByteOrdered};
+use crate::error::Error;
+use crate::{Receiver, Result, Sender};
+
+///Receiver which reads at most len bytes from the inner receiver.
+/// Reading more is an error (io::ErrorKind::UnexpectedEof, Error::Eof for no_std), skip_rest() consumes the rest.
+pub struct Bounded<'a, R: ?Sized> {
+    inner: &'a mut R,
+    left: usize,
+}
+
+impl<'a, R: ?Sized> Bounded<'a, R> {
+    #[inline]
+    pub fn new(inner: &'a mut R, len: usize) -> Self {
+        Self { inner, left: len }
+    }
+
+    ///The bytes can be read
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.left
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &*self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut *self.inner
+    }
+
+    #[inline]
+    pub(crate) fn take(&mut self, len: usize) -> Result<()> {
+        if len > self.left {
+            return Err(crate::error::eof());
+        }
+        self.left -= len;
+        Ok(())
+    }
+}
+
+impl<R: Receiver + ?Sized> Bounded<'_, R> {
+    ///Skip the bytes which are not read
+    pub fn skip_rest(&mut self) -> Result<()> {
+        let mut buf = [0; 256];
+        while self.left > 0 {
+            let n = self.left.min(buf.len());
+            self.rcv_all(&mut buf[..n])?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Receiver + ?Sized> Receiver for Bounded<'_, R> {
+    #[inline]
+    fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.take(buf.len())?;
+        self.inner.rcv_all(buf)
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.inner.byte_order()
+    }
+
+    #[inline]
+    fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        self.take(len)?;
+        self.inner.rcv_bytes(len)
+    }
+
+    #[inline]
+    fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        self.rcv_bytes(self.left)
+    }
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        self.inner.position()
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        self.inner.is_strict()
+    }
+}
+
+impl<'a, R: RefReceiver<'a> + ?Sized> RefReceiver<'a> for Bounded<'_, R> {
+    #[inline]
+    fn rcv_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        self.take(len)?;
+        self.inner.rcv_slice(len)
+    }
+}
+
+///Send the data with exactly size bytes: Error::DataTooLarge if they're longer, padded with 0 if they're shorter.
+/// The data are serialized with the byte order of the writer.
+pub fn snd_sized<S, F>(writer: &mut S, size: usize, f: F) -> Result<()>
+where
+    S: Sender + ?Sized,
+    F: FnOnce(&mut ByteOrdered<Vec<u8>>) -> Result<()>,
+{
+    let mut buf = ByteOrdered::new(Vec::with_capacity(size), writer.byte_order());
+    f(&mut buf)?;
+    let mut buf = buf.into_inner();
+    if buf.len() > size {
+        return Err(Error::DataTooLarge {
+            len: buf.len(),
+            max: size,
+        });
+    }
+    buf.resize(size, 0);
+    writer.snd_all(&buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Rcv, RcvRef, Snd};
+
+    #[test]
+    fn test_bounded() -> Result<()> {
+        let data = [1_u8, 0, 2, 0, 3, 0, 4, 0];
+        let mut buf = &data[..];
+
+        let mut r = Bounded::new(&mut buf, 6);
+        let v: [u8; 4] = r.rcv()?;
+        assert_eq!(v, [1, 0, 2, 0]);
+        assert_eq!(r.remaining(), 2);
+        //over-read
+        assert!(r.rcv::<u32>().is_err());
+        r.skip_rest()?;
+        assert_eq!(r.remaining(), 0);
+        assert_eq!(buf, [4, 0]);
+
+        let mut r = Bounded::new(&mut buf, 1);
+        assert!(r.rcv_ref::<u16>().is_err());
+        assert!(r.rcv_slice(1).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_snd_sized() -> Result<()> {
+        let mut vec = Vec::new();
+        snd_sized(&mut vec, 4, |w| w.snd(7_u8))?;
+        assert_eq!(vec, [7, 0, 0, 0]);
+        assert!(matches!(
+            snd_sized(&mut vec, 1, |w| w.snd(7_u16)),
+            Err(Error::DataTooLarge { len: 2, max: 1 })
+        ));
+        assert_eq!(vec.len(), 4);
+        Ok(())
+    }
+}
@@ -1,20 +1,252 @@
-//! endian create, includes some helpful type: UTF16, VLQ, Ascii etc and endiness/reuseable implmentations
+//! endian create, includes some helpful type: UTF16, VLQ, LEB128, Ascii etc and endiness/reuseable implmentations
 
 #[cfg(all(feature = "big", feature = "little"))]
 compile_error!("have both big or little feature");
-#[cfg(all(feature = "len_16", feature = "len_vlq"))]
-compile_error!("have both len_16 or len_vlq feature");
+#[cfg(any(
+    all(feature = "len_16", feature = "len_vlq"),
+    all(feature = "len_16", feature = "len_leb128"),
+    all(feature = "len_vlq", feature = "len_leb128")
+))]
+compile_error!("have more than one of len_16, len_vlq and len_leb128 features");
 
 pub mod big;
 pub mod impl_macro;
+pub(crate) mod leb128;
 pub mod little;
 
-use std::collections::{BTreeMap, HashMap, LinkedList, VecDeque};
-use std::{ffi::CString, hash::Hash, ops::Deref};
+pub use leb128::{SLeb128, ULeb128, ZigZag};
+
+use alloc::collections::{BTreeMap, LinkedList, VecDeque};
+use alloc::{
+    ffi::CString,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{hash::Hash, ops::Deref};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 use crate::{impl_tuple, snd_ref};
 use crate::{FromReader, IntoWriter, Receivable, Receiver, Sendable, Sender};
 
+use big::{ReceivableBE, SendableBE};
+use little::{ReceivableLE, SendableLE};
+
+///Byte order for numbers, Length, UTF16 etc.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    ///From the "little" or "big" feature, native byte order if there is neither.
+    pub const DEFAULT: ByteOrder = if cfg!(feature = "little") {
+        ByteOrder::Little
+    } else if cfg!(any(feature = "big", target_endian = "big")) {
+        ByteOrder::Big
+    } else {
+        ByteOrder::Little
+    };
+}
+
+impl Default for ByteOrder {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+///Sender/Receiver with the byte order selected at runtime.
+/// ```
+/// use resend::{endian::{ByteOrder, ByteOrdered}, Rcv, Snd};
+///
+/// let mut vec = Vec::new();
+/// ByteOrdered::new(&mut vec, ByteOrder::Big).snd(0x0102_u16)?;
+/// assert_eq!(vec, [1, 2]);
+///
+/// let v: u16 = ByteOrdered::new(&vec[..], ByteOrder::Big).rcv()?;
+/// assert_eq!(v, 0x0102);
+/// # Ok::<(), resend::error::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct ByteOrdered<T> {
+    pub(crate) inner: T,
+    pub(crate) order: ByteOrder,
+}
+
+impl<T> ByteOrdered<T> {
+    #[inline]
+    pub fn new(inner: T, order: ByteOrder) -> Self {
+        Self { inner, order }
+    }
+
+    #[inline]
+    pub fn little(inner: T) -> Self {
+        Self::new(inner, ByteOrder::Little)
+    }
+
+    #[inline]
+    pub fn big(inner: T) -> Self {
+        Self::new(inner, ByteOrder::Big)
+    }
+
+    #[inline]
+    pub fn set_byte_order(&mut self, order: ByteOrder) {
+        self.order = order;
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Sender> Sender for ByteOrdered<T> {
+    #[inline]
+    fn snd_all(&mut self, buf: &[u8]) -> crate::Result<()> {
+        self.inner.snd_all(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> crate::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.order
+    }
+}
+
+impl<T: Receiver> Receiver for ByteOrdered<T> {
+    #[inline]
+    fn rcv_all(&mut self, buf: &mut [u8]) -> crate::Result<()> {
+        self.inner.rcv_all(buf)
+    }
+
+    #[inline]
+    fn rcv_bytes(&mut self, len: usize) -> crate::Result<Vec<u8>> {
+        self.inner.rcv_bytes(len)
+    }
+
+    #[inline]
+    fn rcv_rest(&mut self) -> crate::Result<Vec<u8>> {
+        self.inner.rcv_rest()
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.order
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        self.inner.position()
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        self.inner.is_strict()
+    }
+}
+
+///The borrowed sender/receiver with the fixed byte order, LE(v) and BE(v) send/receive v with it
+pub(crate) struct OrderedRef<'a, T: ?Sized> {
+    pub(crate) inner: &'a mut T,
+    pub(crate) order: ByteOrder,
+}
+
+impl<'a, T: ?Sized> OrderedRef<'a, T> {
+    #[inline]
+    pub(crate) fn new(inner: &'a mut T, order: ByteOrder) -> Self {
+        Self { inner, order }
+    }
+}
+
+impl<T: Sender + ?Sized> Sender for OrderedRef<'_, T> {
+    #[inline]
+    fn snd_all(&mut self, buf: &[u8]) -> crate::Result<()> {
+        self.inner.snd_all(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> crate::Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.order
+    }
+}
+
+impl<T: Receiver + ?Sized> Receiver for OrderedRef<'_, T> {
+    #[inline]
+    fn rcv_all(&mut self, buf: &mut [u8]) -> crate::Result<()> {
+        self.inner.rcv_all(buf)
+    }
+
+    #[inline]
+    fn rcv_bytes(&mut self, len: usize) -> crate::Result<Vec<u8>> {
+        self.inner.rcv_bytes(len)
+    }
+
+    #[inline]
+    fn rcv_rest(&mut self) -> crate::Result<Vec<u8>> {
+        self.inner.rcv_rest()
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.order
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        self.inner.position()
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        self.inner.is_strict()
+    }
+}
+
+//Numbers etc. follow the byte order of the Sender
+impl<T: SendableLE + SendableBE> Sendable for T {
+    #[inline]
+    fn snd_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
+        match writer.byte_order() {
+            ByteOrder::Little => SendableLE::send_to(self, writer),
+            ByteOrder::Big => SendableBE::send_to(self, writer),
+        }
+    }
+}
+
+//Numbers etc. follow the byte order of the Receiver
+impl<T: ReceivableLE + ReceivableBE> Receivable for T {
+    #[inline]
+    fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self> {
+        match reader.byte_order() {
+            ByteOrder::Little => <T as ReceivableLE>::receive_from(reader),
+            ByteOrder::Big => <T as ReceivableBE>::receive_from(reader),
+        }
+    }
+}
+
 ///UTF16 char
 #[derive(PartialEq, Eq, Debug)]
 pub struct UTF16Char(pub char);
@@ -89,6 +321,15 @@ impl Deref for VLQ {
     }
 }
 
+impl Length {
+    ///ULeb128 length for the "len_leb128" feature, Error::Overflow if it doesn't fit in usize
+    #[cfg(feature = "len_leb128")]
+    #[inline]
+    pub(crate) fn from_leb128(v: ULeb128) -> crate::Result<usize> {
+        usize::try_from(v.0).map_err(|_| crate::error::Error::Overflow("length overflows usize"))
+    }
+}
+
 impl Deref for Length {
     type Target = usize;
 
@@ -114,7 +355,10 @@ impl Length {
             feature = "MAX_LEN_2G"
         ))]
         if self.0 > Self::MAX_LEN {
-            return Err(crate::error::Error::DataTooLarge(Self::MAX_LEN));
+            return Err(crate::error::Error::DataTooLarge {
+                len: self.0,
+                max: Self::MAX_LEN,
+            });
         }
 
         Ok(())
@@ -183,7 +427,18 @@ impl Receivable for bool {
     {
         let mut buf = [0];
         reader.rcv_all(&mut buf)?;
-        Ok(buf[0] != 0)
+        bool_from_byte(buf[0], reader.is_strict())
+    }
+}
+
+///Any non-zero byte is true, only 0 or 1 in strict mode
+#[inline]
+pub(crate) fn bool_from_byte(b: u8, strict: bool) -> crate::Result<bool> {
+    match b {
+        0 => Ok(false),
+        1 => Ok(true),
+        b if strict => Err(crate::error::Error::InvalidBool(b)),
+        _ => Ok(true),
     }
 }
 
@@ -191,23 +446,15 @@ impl Sendable for Length {
     fn snd_to<S>(&self, writer: &mut S) -> crate::Result<()>
     where
         S: Sender {
-        #[cfg(not(any(feature = "len_vlq", feature = "len_16")))]
-        if cfg!(feature = "little") {
-            writer.snd_all(&(self.0 as u32).to_le_bytes())?;
-        } else if cfg!(feature = "big"){
-            writer.snd_all(&(self.0 as u32).to_be_bytes())?;
-        } else {
-            writer.snd_all(&(self.0 as u32).to_ne_bytes())?;
-        }
+        #[cfg(not(any(feature = "len_vlq", feature = "len_16", feature = "len_leb128")))]
+        (self.0 as u32).snd_to(writer)?;
         
         #[cfg(feature = "len_16")]
-        if cfg!(feature = "little") {
-            writer.snd_all(&(self.0 as u16).to_le_bytes())?;
-        } else {
-            writer.snd_all(&(self.0 as u16).to_be_bytes())?;
-        }
+        (self.0 as u16).snd_to(writer)?;
         #[cfg(feature = "len_vlq")]
         VLQ(self.0).snd_to(writer)?;
+        #[cfg(feature = "len_leb128")]
+        ULeb128(self.0 as u64).snd_to(writer)?;
 
         Ok(())
     }
@@ -219,40 +466,23 @@ impl Receivable for Length {
     fn rcv_from<R>(reader: &mut R) -> crate::Result<Self>
     where
         R: Receiver {
-            #[cfg(not(any(feature = "len_vlq", feature = "len_16")))]
-            let len = {
-                let mut v = [0; 4];
-                reader.rcv_all(&mut v)?;
-                if cfg!(feature = "little") {
-                    u32::from_le_bytes(v) as usize
-                } else if cfg!(feature = "big"){
-                    u32::from_be_bytes(v) as usize
-                } else {
-                    u32::from_ne_bytes(v) as usize
-                }
-            };
+            #[cfg(not(any(feature = "len_vlq", feature = "len_16", feature = "len_leb128")))]
+            let len = u32::rcv_from(reader)? as usize;
             
             #[cfg(feature = "len_16")]
-            let len = {
-                let mut v = [0; 2];
-                reader.rcv_all(&mut v)?;
-                if cfg!(feature = "little") {
-                    u16::from_le_bytes(v) as usize
-                } else {
-                    u16::from_be_bytes(v) as usize
-                }
-            };
+            let len = u16::rcv_from(reader)? as usize;
             #[cfg(feature = "len_vlq")]
             let len = {
                 let vlq: VLQ = VLQ::rcv_from(reader)?;
-                vlq.0 as usize
+                vlq.0
             };
+            #[cfg(feature = "len_leb128")]
+            let len = Length::from_leb128(ULeb128::rcv_from(reader)?)?;
     
             Ok(Length(len))
         }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
 impl Sendable for char {
     #[inline]
     fn snd_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
@@ -260,10 +490,8 @@ impl Sendable for char {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
 snd_ref!(&char);
 
-#[cfg(any(feature = "little", feature = "big"))]
 impl Receivable for char {
     #[inline]
     fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self>
@@ -303,18 +531,41 @@ impl Sendable for VLQ {
 
 snd_ref!(&VLQ);
 
+impl VLQ {
+    ///Max bytes for usize
+    const MAX_BYTES: usize = (usize::BITS as usize).div_ceil(7);
+
+    ///Add the n-th byte to the value: Error::Overflow if it's too long or overflows usize,
+    /// Error::NonCanonical for the leading 0x80 in strict mode.
+    #[inline]
+    pub(crate) fn push_byte(v: usize, b: u8, n: usize, strict: bool) -> crate::Result<usize> {
+        if strict && n == 0 && b == 0x80 {
+            return Err(crate::error::Error::NonCanonical("VLQ with leading 0x80"));
+        }
+        if n >= Self::MAX_BYTES {
+            return Err(crate::error::Error::Overflow("VLQ is too long"));
+        }
+        if v >> (usize::BITS - 7) != 0 {
+            return Err(crate::error::Error::Overflow("VLQ overflows usize"));
+        }
+        Ok((v << 7) | (b & 127) as usize)
+    }
+}
+
 impl Receivable for VLQ {
     #[inline]
     fn rcv_from<R>(reader: &mut R) -> crate::Result<Self>
     where
         R: Receiver,
     {
+        let strict = reader.is_strict();
         let mut buf = [0];
         let mut v = 0;
+        let mut n = 0;
         loop {
             reader.rcv_all(&mut buf)?;
-
-            v = (v << 7) | (buf[0] & 127) as usize;
+            v = VLQ::push_byte(v, buf[0], n, strict)?;
+            n += 1;
 
             let last = (buf[0] & 128) == 0;
 
@@ -376,12 +627,13 @@ impl Receivable for String {
     {
         let len = *Length::rcv_from(reader)?;
         let buffer = reader.rcv_bytes(len)?;
-        let s = std::str::from_utf8(&buffer)?;
+        let s = core::str::from_utf8(&buffer)?;
         Ok(s.to_string())
     }
 }
 
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a> Sendable for &'a str {
     #[inline]
     fn snd_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
@@ -392,6 +644,18 @@ impl<'a> Sendable for &'a str {
 
 
 
+//the same as Vec<T>, &[u8] can be received with RcvRef without allocation
+impl<T: Sendable> Sendable for &[T] {
+    #[inline]
+    fn snd_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
+        Length(self.len()).snd_to(writer)?;
+        for v in self.iter() {
+            v.snd_to(writer)?;
+        }
+        Ok(())
+    }
+}
+
 impl<T, const N: usize> Sendable for [T; N]
 where
     T: Sendable,
@@ -591,6 +855,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<K, V> Sendable for HashMap<K, V>
 where
     K: Sendable + Eq + Hash,
@@ -608,6 +873,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<K, V> Sendable for &HashMap<K, V>
 where
     K: Sendable + Eq + Hash,
@@ -621,6 +887,7 @@ where
 }
 
 
+#[cfg(feature = "std")]
 impl<K, V> Receivable for HashMap<K, V>
 where
     K: Receivable + Eq + Hash,
@@ -723,8 +990,19 @@ impl Receivable for Ascii {
     {
         let len = *Length::rcv_from(reader)?;
         let buf = reader.rcv_bytes(len)?;
-        let mut s = String::with_capacity(len);
-        for a in buf {
+        Ascii::from_bytes(&buf, reader.is_strict())
+    }
+}
+
+impl Ascii {
+    ///Bytes above 127 are Latin-1 chars, they're errors in strict mode
+    #[inline]
+    pub(crate) fn from_bytes(buf: &[u8], strict: bool) -> crate::Result<Self> {
+        let mut s = String::with_capacity(buf.len());
+        for &a in buf {
+            if strict && !a.is_ascii() {
+                return Err(crate::error::Error::InvalidAscii(format!("u8: {}", a)));
+            }
             if let Some(c) = char::from_u32(a as u32) {
                 s.push(c);
             } else {
@@ -736,7 +1014,6 @@ impl Receivable for Ascii {
 }
 
 
-#[cfg(any(feature = "little", feature = "big"))]
 impl Sendable for usize {
     #[inline]
     fn snd_to<S>(&self, writer: &mut S) -> crate::Result<()>
@@ -747,10 +1024,8 @@ impl Sendable for usize {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
 snd_ref!(&usize);
 
-#[cfg(any(feature = "little", feature = "big"))]
 impl Receivable for usize {
     #[inline]
     fn rcv_from<R>(reader: &mut R) -> crate::Result<Self>
@@ -761,7 +1036,6 @@ impl Receivable for usize {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
 impl Sendable for isize {
     #[inline]
     fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
@@ -769,10 +1043,8 @@ impl Sendable for isize {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
 snd_ref!(&isize);
 
-#[cfg(any(feature = "little", feature = "big"))]
 impl Receivable for isize {
     #[inline]
     fn rcv_from<R>(reader: &mut R) -> crate::Result<Self>
@@ -784,19 +1056,16 @@ impl Receivable for isize {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Sendable for std::num::NonZeroU16 {
+impl Sendable for core::num::NonZeroU16 {
     #[inline]
     fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
         self.get().snd_to(writer)
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-snd_ref!(&std::num::NonZeroU16);
+snd_ref!(&core::num::NonZeroU16);
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Receivable for std::num::NonZeroU16 {
+impl Receivable for core::num::NonZeroU16 {
     #[inline]
     fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self>
     where
@@ -806,19 +1075,16 @@ impl Receivable for std::num::NonZeroU16 {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Sendable for std::num::NonZeroI16 {
+impl Sendable for core::num::NonZeroI16 {
     #[inline]
     fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
         self.get().snd_to(writer)
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-snd_ref!(&std::num::NonZeroI16);
+snd_ref!(&core::num::NonZeroI16);
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Receivable for std::num::NonZeroI16 {
+impl Receivable for core::num::NonZeroI16 {
     #[inline]
     fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self>
     where
@@ -828,19 +1094,16 @@ impl Receivable for std::num::NonZeroI16 {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Sendable for std::num::NonZeroU32 {
+impl Sendable for core::num::NonZeroU32 {
     #[inline]
     fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
         self.get().snd_to(writer)
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-snd_ref!(&std::num::NonZeroU32);
+snd_ref!(&core::num::NonZeroU32);
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Receivable for std::num::NonZeroU32 {
+impl Receivable for core::num::NonZeroU32 {
     #[inline]
     fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self>
     where
@@ -850,19 +1113,16 @@ impl Receivable for std::num::NonZeroU32 {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Sendable for std::num::NonZeroI32 {
+impl Sendable for core::num::NonZeroI32 {
     #[inline]
     fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
         self.get().snd_to(writer)
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-snd_ref!(&std::num::NonZeroI32);
+snd_ref!(&core::num::NonZeroI32);
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Receivable for std::num::NonZeroI32 {
+impl Receivable for core::num::NonZeroI32 {
     #[inline]
     fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self>
     where
@@ -872,19 +1132,16 @@ impl Receivable for std::num::NonZeroI32 {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Sendable for std::num::NonZeroU64 {
+impl Sendable for core::num::NonZeroU64 {
     #[inline]
     fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
         self.get().snd_to(writer)
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-snd_ref!(&std::num::NonZeroU64);
+snd_ref!(&core::num::NonZeroU64);
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Receivable for std::num::NonZeroU64 {
+impl Receivable for core::num::NonZeroU64 {
     #[inline]
     fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self>
     where
@@ -894,19 +1151,16 @@ impl Receivable for std::num::NonZeroU64 {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Sendable for std::num::NonZeroUsize {
+impl Sendable for core::num::NonZeroUsize {
     #[inline]
     fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
         self.get().snd_to(writer)
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-snd_ref!(&std::num::NonZeroUsize);
+snd_ref!(&core::num::NonZeroUsize);
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Receivable for std::num::NonZeroUsize {
+impl Receivable for core::num::NonZeroUsize {
     #[inline]
     fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self>
     where
@@ -916,19 +1170,16 @@ impl Receivable for std::num::NonZeroUsize {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Sendable for std::num::NonZeroU128 {
+impl Sendable for core::num::NonZeroU128 {
     #[inline]
     fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
         self.get().snd_to(writer)
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
-snd_ref!(&std::num::NonZeroU128);
+snd_ref!(&core::num::NonZeroU128);
 
-#[cfg(any(feature = "little", feature = "big"))]
-impl Receivable for std::num::NonZeroU128 {
+impl Receivable for core::num::NonZeroU128 {
     #[inline]
     fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self>
     where
@@ -958,7 +1209,7 @@ impl FromReader for String {
         }
 
         let b = reader.rcv_bytes(len)?;
-        let mut s = std::str::from_utf8(&b)?.to_string();
+        let mut s = core::str::from_utf8(&b)?.to_string();
         while s.ends_with('\0') {
             s.truncate(s.len() - 1); //String is UTF8
         }
@@ -967,6 +1218,13 @@ impl FromReader for String {
 }
 
 impl IntoWriter for String {
+    #[inline]
+    fn into_writer<S: Sender>(&self, writer: &mut S, len: usize) -> crate::Result<()> {
+        self.as_str().into_writer(writer, len)
+    }
+}
+
+impl IntoWriter for &str {
     #[inline]
     fn into_writer<S: Sender>(&self, writer: &mut S, len: usize) -> crate::Result<()> {
         let len_s = self.len();
@@ -998,6 +1256,9 @@ impl FromReader for Ascii {
     #[inline]
     fn from_reader<R: Receiver>(reader: &mut R, len: usize) -> crate::Result<Self> {
         let s = String::from_reader(reader, len)?;
+        if reader.is_strict() && !s.is_ascii() {
+            return Err(crate::error::Error::InvalidAscii(s));
+        }
         Ok(Ascii(s))
     }
 }
@@ -1030,6 +1291,13 @@ impl<T: Receivable> FromReader for Vec<T> {
 }
 
 impl<T: Sendable + Default> IntoWriter for Vec<T> {
+    #[inline]
+    fn into_writer<S: Sender>(&self, writer: &mut S, len: usize) -> crate::Result<()> {
+        self.as_slice().into_writer(writer, len)
+    }
+}
+
+impl<T: Sendable + Default> IntoWriter for &[T] {
     #[inline]
     fn into_writer<S: Sender>(&self, writer: &mut S, len: usize) -> crate::Result<()> {
         let len_s = self.len();
@@ -1059,7 +1327,6 @@ impl<T: Sendable + Default> IntoWriter for &Vec<T> {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
 impl FromReader for UTF16 {
     #[inline]
     fn from_reader<R: Receiver>(reader: &mut R, mut len: usize) -> crate::Result<Self> {
@@ -1082,7 +1349,6 @@ impl FromReader for UTF16 {
     }
 }
 
-#[cfg(any(feature = "little", feature = "big"))]
 impl IntoWriter for UTF16 {
     #[inline]
     fn into_writer<S: Sender>(&self, writer: &mut S, mut len: usize) -> crate::Result<()> {
@@ -1105,15 +1371,22 @@ impl IntoWriter for UTF16 {
 }
 
 #[cfg(test)]
+//the tests run without the "little"/"big" feature too
+#[allow(
+    clippy::needless_borrow,
+    clippy::needless_borrows_for_generic_args,
+    clippy::unnecessary_cast,
+    clippy::assertions_on_constants,
+    clippy::char_lit_as_u8
+)]
 mod tests {
     use crate::{
         endian::{LE, UTF16Char, UTF16, VLQ},
         error::Error,
-        Rcv, Snd,
+        Rcv, Receiver, Snd,
     };
     use std::ffi::CString;
 
-    #[cfg(any(feature = "big", feature = "little"))]
     #[test]
     fn test_default() -> crate::Result<()> {
         let mut vec = Vec::new();
@@ -1207,10 +1480,13 @@ mod tests {
         let rst = b.rcv::<LE<u16>>();
         match rst {
             Ok(_) => assert!(false),
+            #[cfg(feature = "std")]
             Err(Error::Io(e)) => {
                 println!("Err {:?}", e);
                 assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
             }
+            #[cfg(not(feature = "std"))]
+            Err(Error::Eof) => {}
             Err(_) => {
                 panic!("Wrong error kind");
             }
@@ -1311,11 +1587,21 @@ mod tests {
         let v: VLQ = buf.rcv()?;
         assert_eq!(0x0FFFFFFF, *v);
 
+        //endless continuation bytes (leading 0x80 is an error in strict mode) and usize overflow
+        #[cfg(not(feature = "strict"))]
+        {
+            let data = [0x80_u8; 20];
+            assert!(matches!((&data[..]).rcv::<VLQ>(), Err(Error::Overflow(_))));
+        }
+        let data = [0x82_u8, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F];
+        assert!(matches!((&data[..]).rcv::<VLQ>(), Err(Error::Overflow(_))));
+        let data = [0x81_u8, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F];
+        assert_eq!(*(&data[..]).rcv::<VLQ>()?, usize::MAX);
+
         Ok(())
     }
 
     #[test]
-    #[cfg(any(feature = "big", feature = "little"))]
     fn test_utf16_from() -> crate::Result<()> {
         use crate::endian::Length;
         use crate::{FromReader, IntoWriter};
@@ -1324,11 +1610,11 @@ mod tests {
 
         vec.snd(UTF16(("2欢迎2𝌆𠮷").to_string()))?;
 
-        #[cfg(not(any(feature = "len_vlq", feature = "len_16")))]
+        #[cfg(not(any(feature = "len_vlq", feature = "len_16", feature = "len_leb128")))]
         assert_eq!(16 + 4, vec.len());
         #[cfg(feature = "len_16")]
         assert_eq!(16 + 2, vec.len());
-        #[cfg(feature = "len_vlq")]
+        #[cfg(any(feature = "len_vlq", feature = "len_leb128"))]
         assert_eq!(16 + 1, vec.len());
 
         let u = UTF16(String::from("Test"));
@@ -1364,7 +1650,6 @@ mod tests {
         Ok(())
     }
     #[test]
-    #[cfg(any(feature = "big", feature = "little"))]
     fn test_tuple() -> crate::Result<()> {
         let t = (1_u16, 2_u32);
 
@@ -1381,7 +1666,6 @@ mod tests {
     }
 
     #[test]
-    #[cfg(any(feature = "big", feature = "little"))]
     fn test_range() -> crate::Result<()> {
         use std::ops::Range;
 
@@ -1415,7 +1699,6 @@ mod tests {
     }
 
     #[test]
-    #[cfg(any(feature = "big", feature = "little"))]
     fn test_vec_u8() -> crate::Result<()> {
         let b = vec![1_u8, 2, 3];
 
@@ -1429,4 +1712,34 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_byte_order() -> crate::Result<()> {
+        use crate::endian::{ByteOrder, ByteOrdered};
+
+        let mut vec = Vec::new();
+        let mut w = ByteOrdered::big(&mut vec);
+        w.snd(0x0102_u16)?;
+        w.snd(UTF16("a".to_string()))?;
+        w.set_byte_order(ByteOrder::Little);
+        w.snd(0x0102_u16)?;
+        w.snd(LE(0x0304_u16))?;
+
+        #[cfg(not(any(feature = "len_vlq", feature = "len_16", feature = "len_leb128")))]
+        assert_eq!(vec, [1, 2, 0, 0, 0, 2, 0, b'a', 2, 1, 4, 3]);
+
+        let mut r = ByteOrdered::big(&vec[..]);
+        assert_eq!(ByteOrder::Big, r.byte_order());
+        let v: u16 = r.rcv()?;
+        assert_eq!(v, 0x0102);
+        let v: UTF16 = r.rcv()?;
+        assert_eq!(*v, "a");
+        r.set_byte_order(ByteOrder::Little);
+        let v: u16 = r.rcv()?;
+        assert_eq!(v, 0x0102);
+        let v: LE<u16> = r.rcv()?;
+        assert_eq!(*v, 0x0304);
+
+        Ok(())
+    }
 }
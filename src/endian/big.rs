@@ -1,14 +1,12 @@
 //! Big-endian type (BE) and implmentations
-use std::{
-    borrow::Cow,
-    ops::Range,
-    path::PathBuf,
-    time::Duration,
-};
+use alloc::{borrow::Cow, boxed::Box, string::String};
+use core::{ops::Range, time::Duration};
+#[cfg(feature = "std")]
+use std::path::PathBuf;
 
 use crate::{Receivable, Receiver, Sendable, Sender};
 
-use super::{Length, UTF16Char, BE, UTF16};
+use super::{ByteOrder, Length, OrderedRef, UTF16Char, BE, UTF16};
 
 pub trait SendableBE {
     fn send_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()>;
@@ -20,28 +18,24 @@ pub trait ReceivableBE {
         Self: Sized;
 }
 
-impl<T: SendableBE> Sendable for BE<T> {
+//Any Sendable: the numbers, Length etc. inside (the elements of Vec, the fields of derived types) are big-endian too
+impl<T: Sendable> Sendable for BE<T> {
     #[inline]
     fn snd_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
-        self.0.send_to(writer)
+        let mut writer = OrderedRef::new(writer, ByteOrder::Big);
+        self.0.snd_to(&mut writer)
     }
 }
 
-impl<T: SendableBE> Sendable for &BE<T> {
+impl<T: Sendable> Sendable for &BE<T> {
     #[inline]
     fn snd_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
-        self.0.send_to(writer)
-    }
-}
-
-#[cfg(feature = "big")]
-impl<T: SendableBE> Sendable for T {
-    #[inline]
-    fn snd_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
-        self.send_to(writer)
+        let mut writer = OrderedRef::new(writer, ByteOrder::Big);
+        self.0.snd_to(&mut writer)
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, T: SendableBE> SendableBE for &'a T {
     #[inline]
     fn send_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
@@ -159,25 +153,15 @@ impl SendableBE for UTF16 {
 
 impl<T> Receivable for BE<T>
 where
-    T: ReceivableBE,
+    T: Receivable,
 {
     #[inline]
     fn rcv_from<R>(reader: &mut R) -> crate::Result<Self>
     where
         R: Receiver,
     {
-        Ok(BE(T::receive_from(reader)?))
-    }
-}
-
-#[cfg(feature = "big")]
-impl<T: ReceivableBE> Receivable for T {
-    #[inline]
-    fn rcv_from<R>(reader: &mut R) -> crate::Result<Self>
-    where
-        R: Receiver,
-    {
-        T::receive_from(reader)
+        let mut reader = OrderedRef::new(reader, ByteOrder::Big);
+        Ok(BE(T::rcv_from(&mut reader)?))
     }
 }
 
@@ -439,6 +423,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl SendableBE for PathBuf {
     #[inline]
     fn send_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
@@ -449,6 +434,7 @@ impl SendableBE for PathBuf {
     }
 }
 
+#[cfg(feature = "std")]
 impl ReceivableBE for PathBuf {
     #[inline]
     fn receive_from<R: Receiver>(reader: &mut R) -> crate::Result<Self> {
@@ -459,7 +445,7 @@ impl ReceivableBE for PathBuf {
 
 #[cfg(test)]
 mod tests {
-    use crate::{endian::big::BE, Snd};
+    use crate::{endian::big::BE, endian::ByteOrdered, Rcv, Snd};
 
     #[test]
     fn test_be_num() -> crate::Result<()> {
@@ -481,4 +467,25 @@ mod tests {
         assert!(vec[7] == 32);
         Ok(())
     }
+
+    #[test]
+    fn test_be_collections() -> crate::Result<()> {
+        let mut vec: Vec<u8> = Vec::new();
+        vec.snd(BE([0x0102_u32, 3]))?;
+        assert_eq!(vec, [0, 0, 1, 2, 0, 0, 0, 3]);
+        vec.clear();
+        vec.snd(BE(Some(7_u32)))?;
+        assert_eq!(vec, [1, 0, 0, 0, 7]);
+
+        //the Length header and every element
+        let v = (vec![1_u16, 0x0203], "ab".to_string());
+        let mut vec: Vec<u8> = Vec::new();
+        vec.snd(BE(v.clone()))?;
+        let mut expected = ByteOrdered::big(Vec::new());
+        expected.snd(v.clone())?;
+        assert_eq!(vec, expected.into_inner());
+        let v1: BE<(Vec<u16>, String)> = vec.as_slice().rcv()?;
+        assert_eq!(v1.0, v);
+        Ok(())
+    }
 }
@@ -0,0 +1,428 @@
+//! LEB128 (little-endian base-128) types: ULeb128 and SLeb128, the encoding of protobuf, WebAssembly and DWARF.
+//!
+//! The low 7 bits come first, at most 10 bytes for 64-bit values.
+//! Length is ULeb128 with the "len_leb128" feature.
+//! ZigZag maps the signed integers to ULeb128 like protobuf sint32/sint64, the #[varint] attribute uses it.
+
+use core::ops::Deref;
+
+use crate::error::Error;
+use crate::{FromReader, IntoWriter, Receivable, Receiver, Sendable, Sender};
+
+///Unsigned LEB128
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ULeb128(pub u64);
+
+///Signed LEB128, the sign bit is the bit 6 of the last byte
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SLeb128(pub i64);
+
+impl Deref for ULeb128 {
+    type Target = u64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Deref for SLeb128 {
+    type Target = i64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+///Max bytes for 64-bit values
+const MAX_BYTES: usize = 10;
+
+impl ULeb128 {
+    ///The bytes and the length
+    #[inline]
+    pub(crate) fn encode(&self) -> ([u8; MAX_BYTES], usize) {
+        let mut buf = [0; MAX_BYTES];
+        let mut v = self.0;
+        let mut n = 0;
+        loop {
+            let b = (v & 127) as u8;
+            v >>= 7;
+            if v == 0 {
+                buf[n] = b;
+                return (buf, n + 1);
+            }
+            buf[n] = b | 128;
+            n += 1;
+        }
+    }
+}
+
+impl SLeb128 {
+    ///The bytes and the length
+    #[inline]
+    pub(crate) fn encode(&self) -> ([u8; MAX_BYTES], usize) {
+        let mut buf = [0; MAX_BYTES];
+        let mut v = self.0;
+        let mut n = 0;
+        loop {
+            let b = (v & 127) as u8;
+            v >>= 7;
+            //the rest is the sign extension of the bit 6
+            if (v == 0 && b & 64 == 0) || (v == -1 && b & 64 != 0) {
+                buf[n] = b;
+                return (buf, n + 1);
+            }
+            buf[n] = b | 128;
+            n += 1;
+        }
+    }
+}
+
+///Decoder for both ULeb128 and SLeb128, the bytes are pushed one by one so it works for async too.
+#[derive(Default)]
+pub(crate) struct Leb128Decoder {
+    value: u64,
+    shift: u32,
+    prev: u8,
+}
+
+impl Leb128Decoder {
+    ///Add the next byte, true if it's the last one.
+    /// Error::Overflow if the value is longer than 64 bits, Error::NonCanonical for padding in strict mode.
+    #[inline]
+    pub(crate) fn push(&mut self, b: u8, signed: bool, strict: bool) -> crate::Result<bool> {
+        if self.shift >= 7 * MAX_BYTES as u32 {
+            return Err(Error::Overflow("LEB128 is longer than 10 bytes"));
+        }
+        let bits = (b & 127) as u64;
+        //1 bit left in the last byte: 0/1 for unsigned, the sign extension for signed
+        let fits = if signed {
+            bits == 0 || bits == 127
+        } else {
+            bits <= 1
+        };
+        if self.shift == 63 && !fits {
+            return Err(Error::Overflow("LEB128 overflows 64 bits"));
+        }
+        self.value |= bits << self.shift;
+        self.shift += 7;
+
+        let last = b & 128 == 0;
+        if last && strict && self.shift > 7 {
+            let padded = if signed {
+                (b == 0 && self.prev & 64 == 0) || (b == 127 && self.prev & 64 != 0)
+            } else {
+                b == 0
+            };
+            if padded {
+                return Err(Error::NonCanonical("LEB128 with padding bytes"));
+            }
+        }
+        self.prev = b;
+        Ok(last)
+    }
+
+    #[inline]
+    pub(crate) fn unsigned(&self) -> ULeb128 {
+        ULeb128(self.value)
+    }
+
+    #[inline]
+    pub(crate) fn signed(&self) -> SLeb128 {
+        if self.shift < 64 && self.prev & 64 != 0 {
+            SLeb128((self.value | (u64::MAX << self.shift)) as i64)
+        } else {
+            SLeb128(self.value as i64)
+        }
+    }
+}
+
+impl ULeb128 {
+    ///Padded to exactly len bytes with 0x80 continuation bytes, for #[len] (the fixed-size LEB128 of WebAssembly relocations)
+    #[inline]
+    pub(crate) fn encode_padded(&self, len: usize) -> crate::Result<([u8; MAX_BYTES], usize)> {
+        let (mut buf, n) = self.encode();
+        if n > len || len > MAX_BYTES {
+            return Err(Error::DataTooLarge {
+                len: n.max(len),
+                max: len.min(MAX_BYTES),
+            });
+        }
+        if len > n {
+            buf[n - 1] |= 128;
+            buf[n..len - 1].fill(128);
+            buf[len - 1] = 0;
+        }
+        Ok((buf, len))
+    }
+
+    ///Decode exactly len bytes, the padding bytes are allowed
+    #[inline]
+    pub(crate) fn decode_padded(buf: &[u8]) -> crate::Result<Self> {
+        let mut decoder = Leb128Decoder::default();
+        for (i, &b) in buf.iter().enumerate() {
+            if decoder.push(b, false, false)? {
+                return match buf.len() - i - 1 {
+                    0 => Ok(decoder.unsigned()),
+                    n => Err(Error::TrailingBytes(n)),
+                };
+            }
+        }
+        Err(crate::error::eof())
+    }
+}
+
+impl Sendable for ULeb128 {
+    #[inline]
+    fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
+        let (buf, n) = self.encode();
+        writer.snd_all(&buf[..n])
+    }
+}
+
+impl Sendable for SLeb128 {
+    #[inline]
+    fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
+        let (buf, n) = self.encode();
+        writer.snd_all(&buf[..n])
+    }
+}
+
+crate::snd_ref!(&ULeb128);
+crate::snd_ref!(&SLeb128);
+
+impl Receivable for ULeb128 {
+    #[inline]
+    fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self> {
+        let strict = reader.is_strict();
+        let mut decoder = Leb128Decoder::default();
+        let mut buf = [0];
+        loop {
+            reader.rcv_all(&mut buf)?;
+            if decoder.push(buf[0], false, strict)? {
+                return Ok(decoder.unsigned());
+            }
+        }
+    }
+}
+
+impl Receivable for SLeb128 {
+    #[inline]
+    fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self> {
+        let strict = reader.is_strict();
+        let mut decoder = Leb128Decoder::default();
+        let mut buf = [0];
+        loop {
+            reader.rcv_all(&mut buf)?;
+            if decoder.push(buf[0], true, strict)? {
+                return Ok(decoder.signed());
+            }
+        }
+    }
+}
+
+///ZigZag encoding for i16, i32 and i64: 0, -1, 1, -2... are 0, 1, 2, 3... in ULeb128.
+/// The #[varint] attribute sends/receives the field with it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ZigZag<T>(pub T);
+
+impl<T> Deref for ZigZag<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+macro_rules! zigzag {
+    ($($t:ty: $u:ty),+ $(,)?) => {
+        $(
+            impl ZigZag<$t> {
+                #[inline]
+                pub(crate) fn to_leb128(self) -> ULeb128 {
+                    let v = self.0;
+                    ULeb128(((v << 1) ^ (v >> (<$t>::BITS - 1))) as $u as u64)
+                }
+
+                #[inline]
+                pub(crate) fn from_leb128(v: ULeb128) -> crate::Result<Self> {
+                    let u = <$u>::try_from(v.0)
+                        .map_err(|_| Error::Overflow(concat!("ZigZag overflows ", stringify!($t))))?;
+                    Ok(ZigZag((u >> 1) as $t ^ -((u & 1) as $t)))
+                }
+            }
+
+            impl Sendable for ZigZag<$t> {
+                #[inline]
+                fn snd_to<S: Sender>(&self, writer: &mut S) -> crate::Result<()> {
+                    self.to_leb128().snd_to(writer)
+                }
+            }
+
+            crate::snd_ref!(&ZigZag<$t>);
+
+            impl Receivable for ZigZag<$t> {
+                #[inline]
+                fn rcv_from<R: Receiver>(reader: &mut R) -> crate::Result<Self> {
+                    Self::from_leb128(ULeb128::rcv_from(reader)?)
+                }
+            }
+
+            //exactly len bytes with padding
+            impl IntoWriter for ZigZag<$t> {
+                #[inline]
+                fn into_writer<S: Sender>(&self, writer: &mut S, len: usize) -> crate::Result<()> {
+                    let (buf, n) = self.to_leb128().encode_padded(len)?;
+                    writer.snd_all(&buf[..n])
+                }
+            }
+
+            impl FromReader for ZigZag<$t> {
+                #[inline]
+                fn from_reader<R: Receiver>(reader: &mut R, len: usize) -> crate::Result<Self> {
+                    if len > MAX_BYTES {
+                        return Err(Error::DataTooLarge { len, max: MAX_BYTES });
+                    }
+                    let mut buf = [0; MAX_BYTES];
+                    reader.rcv_all(&mut buf[..len])?;
+                    Self::from_leb128(ULeb128::decode_padded(&buf[..len])?)
+                }
+            }
+        )+
+    };
+}
+
+zigzag!(i16: u16, i32: u32, i64: u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strict::Strict;
+    use crate::{EncodedLen, Rcv};
+    use crate::{FromReader, IntoWriter};
+    use alloc::vec::Vec;
+
+    fn bytes<T: Sendable + EncodedLen>(v: T) -> crate::Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        v.snd_to(&mut vec)?;
+        assert_eq!(v.encoded_len(), vec.len());
+        Ok(vec)
+    }
+
+    #[test]
+    fn test_uleb128() -> crate::Result<()> {
+        assert_eq!(bytes(ULeb128(0))?, [0]);
+        assert_eq!(bytes(ULeb128(127))?, [0x7F]);
+        assert_eq!(bytes(ULeb128(128))?, [0x80, 1]);
+        assert_eq!(bytes(ULeb128(624485))?, [0xE5, 0x8E, 0x26]);
+
+        for v in [0, 1, 127, 128, 300, 624485, u32::MAX as u64, u64::MAX] {
+            let vec = bytes(ULeb128(v))?;
+            assert_eq!(vec.as_slice().rcv::<ULeb128>()?, ULeb128(v));
+        }
+        assert_eq!(bytes(ULeb128(u64::MAX))?.len(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sleb128() -> crate::Result<()> {
+        assert_eq!(bytes(SLeb128(2))?, [2]);
+        assert_eq!(bytes(SLeb128(-2))?, [0x7E]);
+        assert_eq!(bytes(SLeb128(63))?, [0x3F]);
+        assert_eq!(bytes(SLeb128(64))?, [0xC0, 0]);
+        assert_eq!(bytes(SLeb128(-123456))?, [0xC0, 0xBB, 0x78]);
+
+        for v in [0, 1, -1, 63, -64, 64, -65, i32::MIN as i64, i64::MAX, i64::MIN] {
+            let vec = bytes(SLeb128(v))?;
+            assert_eq!(vec.as_slice().rcv::<SLeb128>()?, SLeb128(v));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_zigzag() -> crate::Result<()> {
+        assert_eq!(bytes(ZigZag(0_i32))?, [0]);
+        assert_eq!(bytes(ZigZag(-1_i32))?, [1]);
+        assert_eq!(bytes(ZigZag(1_i32))?, [2]);
+        assert_eq!(bytes(ZigZag(-64_i16))?, [0x7F]);
+        assert_eq!(bytes(ZigZag(64_i16))?, [0x80, 1]);
+
+        for v in [0, 1, -1, i16::MAX, i16::MIN] {
+            let vec = bytes(ZigZag(v))?;
+            assert_eq!(vec.as_slice().rcv::<ZigZag<i16>>()?, ZigZag(v));
+        }
+        for v in [0, -300, i64::MAX, i64::MIN] {
+            let vec = bytes(ZigZag(v))?;
+            assert_eq!(vec.as_slice().rcv::<ZigZag<i64>>()?, ZigZag(v));
+        }
+        //u16::MAX + 1
+        let vec = bytes(ULeb128(0x10000))?;
+        assert!(matches!(
+            vec.as_slice().rcv::<ZigZag<i16>>(),
+            Err(Error::Overflow(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_zigzag_padded() -> crate::Result<()> {
+        let mut vec = Vec::new();
+        ZigZag(-1_i32).into_writer(&mut vec, 3)?;
+        assert_eq!(vec, [0x81, 0x80, 0]);
+        ZigZag(300_i32).into_writer(&mut vec, 2)?;
+        assert_eq!(vec.len(), 5);
+        assert_eq!(ZigZag(-1_i32).encoded_len_with(5), 5);
+
+        let mut buf = &vec[..];
+        assert_eq!(*ZigZag::<i32>::from_reader(&mut buf, 3)?, -1);
+        assert_eq!(*ZigZag::<i32>::from_reader(&mut buf, 2)?, 300);
+
+        assert!(matches!(
+            ZigZag(300_i32).into_writer(&mut vec, 1),
+            Err(Error::DataTooLarge { len: 2, max: 1 })
+        ));
+        //ends before the len
+        assert!(matches!(
+            ZigZag::<i32>::from_reader(&mut &[1_u8, 0][..], 2),
+            Err(Error::TrailingBytes(1))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_leb128_invalid() -> crate::Result<()> {
+        //endless continuation bytes
+        let data = [0x80_u8; 20];
+        assert!(matches!(
+            data.as_slice().rcv::<ULeb128>(),
+            Err(Error::Overflow(_))
+        ));
+        //more than 64 bits
+        let mut data = [0xFF_u8; 10];
+        data[9] = 2;
+        assert!(matches!(
+            data.as_slice().rcv::<ULeb128>(),
+            Err(Error::Overflow(_))
+        ));
+        assert!(matches!(
+            data.as_slice().rcv::<SLeb128>(),
+            Err(Error::Overflow(_))
+        ));
+
+        //padding is accepted unless it's strict
+        let data = [0x81_u8, 0x80, 0];
+        #[cfg(not(feature = "strict"))]
+        assert_eq!(*data.as_slice().rcv::<ULeb128>()?, 1);
+        assert!(matches!(
+            Strict::new(&data[..]).rcv::<ULeb128>(),
+            Err(Error::NonCanonical(_))
+        ));
+        let data = [0xFF_u8, 0x7F];
+        #[cfg(not(feature = "strict"))]
+        assert_eq!(*data.as_slice().rcv::<SLeb128>()?, -1);
+        assert!(matches!(
+            Strict::new(&data[..]).rcv::<SLeb128>(),
+            Err(Error::NonCanonical(_))
+        ));
+        Ok(())
+    }
+}
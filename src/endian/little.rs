@@ -1,14 +1,12 @@
 //! Little-endian type (LE) and implmentations
-use std::{
-    borrow::Cow,
-    ops::Range,
-    path::PathBuf,
-    time::Duration,
-};
+use alloc::{borrow::Cow, boxed::Box, string::String};
+use core::{ops::Range, time::Duration};
+#[cfg(feature = "std")]
+use std::path::PathBuf;
 
 use crate::{Receivable, Receiver, Sendable, Sender};
 
-use super::{Length, UTF16Char, LE, UTF16};
+use super::{ByteOrder, Length, OrderedRef, UTF16Char, LE, UTF16};
 
 pub trait SendableLE {
     fn send_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()>;
@@ -22,31 +20,24 @@ pub trait ReceivableLE {
 
 
 
-impl<T: SendableLE> Sendable for LE<T> {
+//Any Sendable: the numbers, Length etc. inside (the elements of Vec, the fields of derived types) are little-endian too
+impl<T: Sendable> Sendable for LE<T> {
     #[inline]
     fn snd_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
-        self.0.send_to(writer)
+        let mut writer = OrderedRef::new(writer, ByteOrder::Little);
+        self.0.snd_to(&mut writer)
     }
 }
 
-impl<T: SendableLE> Sendable for &LE<T> {
+impl<T: Sendable> Sendable for &LE<T> {
     #[inline]
     fn snd_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
-        self.0.send_to(writer)
-    }
-}
-
-#[cfg(feature = "little")]
-impl<T: SendableLE> Sendable for T {
-    #[inline]
-    fn snd_to<W>(&self, writer: &mut W) -> crate::Result<()>
-    where
-        W: Sender,
-    {
-        self.send_to(writer)
+        let mut writer = OrderedRef::new(writer, ByteOrder::Little);
+        self.0.snd_to(&mut writer)
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, T: SendableLE> SendableLE for &'a T {
     #[inline]
     fn send_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
@@ -164,25 +155,15 @@ impl SendableLE for UTF16 {
 
 impl<T> Receivable for LE<T>
 where
-    T: ReceivableLE,
+    T: Receivable,
 {
     #[inline]
     fn rcv_from<R>(reader: &mut R) -> crate::Result<Self>
     where
         R: Receiver,
     {
-        Ok(LE(T::receive_from(reader)?))
-    }
-}
-
-#[cfg(feature = "little")]
-impl<T: ReceivableLE> Receivable for T {
-    #[inline]
-    fn rcv_from<R>(reader: &mut R) -> crate::Result<Self>
-    where
-        R: Receiver,
-    {
-        T::receive_from(reader)
+        let mut reader = OrderedRef::new(reader, ByteOrder::Little);
+        Ok(LE(T::rcv_from(&mut reader)?))
     }
 }
 
@@ -444,6 +425,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl SendableLE for PathBuf {
     #[inline]
     fn send_to<W: Sender>(&self, writer: &mut W) -> crate::Result<()> {
@@ -454,6 +436,7 @@ impl SendableLE for PathBuf {
     }
 }
 
+#[cfg(feature = "std")]
 impl ReceivableLE for PathBuf {
     #[inline]
     fn receive_from<R: Receiver>(reader: &mut R) -> crate::Result<Self> {
@@ -465,7 +448,7 @@ impl ReceivableLE for PathBuf {
 
 #[cfg(test)]
 mod tests {
-    use crate::{endian::little::LE, Snd};
+    use crate::{endian::little::LE, endian::ByteOrdered, Rcv, Snd};
 
     #[test]
     fn test_le_num() -> crate::Result<()> {
@@ -482,4 +465,25 @@ mod tests {
         // assert!(vec[3] == 0);
         Ok(())
     }
+
+    #[test]
+    fn test_le_collections() -> crate::Result<()> {
+        let mut vec: Vec<u8> = Vec::new();
+        vec.snd(LE([0x0102_u32, 3]))?;
+        assert_eq!(vec, [2, 1, 0, 0, 3, 0, 0, 0]);
+        vec.clear();
+        vec.snd(LE(Some(7_u32)))?;
+        assert_eq!(vec, [1, 7, 0, 0, 0]);
+
+        //the Length header and every element
+        let v = (vec![1_u16, 0x0203], "ab".to_string());
+        let mut vec: Vec<u8> = Vec::new();
+        vec.snd(LE(v.clone()))?;
+        let mut expected = ByteOrdered::little(Vec::new());
+        expected.snd(v.clone())?;
+        assert_eq!(vec, expected.into_inner());
+        let v1: LE<(Vec<u16>, String)> = vec.as_slice().rcv()?;
+        assert_eq!(v1.0, v);
+        Ok(())
+    }
 }
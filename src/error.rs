@@ -1,6 +1,7 @@
 //! Erros for serializing and deserializing
 
-use std::{error, fmt::Display, str::Utf8Error};
+use alloc::{boxed::Box, format, string::String};
+use core::{fmt::Display, str::Utf8Error};
 
 ///Errors for resend
 #[non_exhaustive]
@@ -8,16 +9,31 @@ use std::{error, fmt::Display, str::Utf8Error};
 pub enum Error {
     ///value is zero for NonzeroInt
     Zero,
-    ///invalid tag value for enum
-    InvalidTag(u32),
+    ///invalid tag value for enum, the tag is cast to u64 (two's complement for signed tags)
+    InvalidTag { ty: &'static str, tag: u64 },
 
     InvalidAscii(String),
-    ///length is too big
-    DataTooLarge(usize),
+    ///length is over the limit: MAX_LEN_* feature, frame max_len, #[size], #[len_of] field type etc.
+    DataTooLarge { len: usize, max: usize },
 
     InvalidChar(u32),
+    ///bool byte other than 0 or 1 (strict mode)
+    InvalidBool(u8),
+    ///the #[when] condition doesn't match the Option field: Some when it's false or None when it's true
+    WhenMismatch { field: &'static str, condition: bool },
+    ///the value of the #[bits(n)] field doesn't fit in n bits
+    BitsOverflow { field: &'static str, bits: u32 },
+    ///the data left after the value is received (strict mode)
+    TrailingBytes(usize),
+    ///the data have another encoding: padded VLQ etc. (strict mode)
+    NonCanonical(&'static str),
+    ///the variable-length integer is too long or doesn't fit in the type
+    Overflow(&'static str),
 
+    #[cfg(feature = "std")]
     Io(std::io::Error),
+    ///no more data to receive or no space to send (no_std)
+    Eof,
 
     Utf8(Utf8Error),
     //general error number
@@ -25,26 +41,121 @@ pub enum Error {
 
     ///New kind will be added to replace Other
     Other(&'static str),
+
+    ///Error from the Sendable/Receivable implementations of the users
+    Custom(Box<dyn core::error::Error + Send + Sync>),
+
+    ///Decoding error of the field, for example: "IoPrinter.device.dos_name".
+    /// The offset is the start of the field if the receiver tracks it (tracked::Tracked).
+    Context {
+        path: String,
+        offset: Option<usize>,
+        source: Box<Error>,
+    },
 }
 
 use Error::*;
 
+impl Error {
+    ///Wrap the error of the user implementations
+    #[inline]
+    pub fn custom<E: Into<Box<dyn core::error::Error + Send + Sync>>>(e: E) -> Error {
+        Custom(e.into())
+    }
+
+    ///Add the field path and offset to the error, #[derive(Rcv)] calls it when a field fails.
+    /// The path of the inner struct is appended to the outer field: "Outer.inner" + "Inner.value" is "Outer.inner.value",
+    /// the enum variant is kept: "Outer.msg" + "Message::Data.payload" is "Outer.msg::Data.payload".
+    pub fn in_field(self, path: &str, offset: Option<usize>) -> Error {
+        match self {
+            Context {
+                path: inner,
+                offset: inner_offset,
+                source,
+            } => {
+                //the inner type name is replaced by the outer path
+                let rest = inner.find(['.', ':']).map_or("", |i| &inner[i..]);
+                Context {
+                    path: format!("{}{}", path, rest),
+                    offset: inner_offset.or(offset),
+                    source,
+                }
+            }
+            e => Context {
+                path: path.into(),
+                offset,
+                source: Box::new(e),
+            },
+        }
+    }
+
+    ///The error without the field context
+    pub fn root(&self) -> &Error {
+        match self {
+            Context { source, .. } => source.root(),
+            e => e,
+        }
+    }
+}
+
 impl Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{:?}", self)
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Zero => write!(f, "zero value for the non-zero integer"),
+            InvalidTag { ty, tag } => write!(f, "invalid tag {} for {}", tag, ty),
+            InvalidAscii(s) => write!(f, "invalid ASCII: {}", s),
+            DataTooLarge { len, max } => write!(f, "length {} is over the limit {}", len, max),
+            InvalidChar(c) => write!(f, "invalid char: {:#x}", c),
+            InvalidBool(b) => write!(f, "invalid bool byte: {}", b),
+            WhenMismatch { field, condition } => write!(
+                f,
+                "the field {} is {} but its #[when] condition is {}",
+                field,
+                if *condition { "None" } else { "Some" },
+                condition
+            ),
+            BitsOverflow { field, bits } => {
+                write!(f, "the field {} doesn't fit in {} bits", field, bits)
+            }
+            TrailingBytes(n) => write!(f, "{} trailing bytes after the value", n),
+            NonCanonical(s) => write!(f, "non-canonical encoding: {}", s),
+            Overflow(s) => write!(f, "overflow: {}", s),
+            #[cfg(feature = "std")]
+            Io(e) => write!(f, "I/O error: {}", e),
+            Eof => write!(f, "unexpected end of data"),
+            Utf8(e) => write!(f, "invalid UTF-8: {}", e),
+            ErrNo(n) => write!(f, "error number {}", n),
+            Other(s) => write!(f, "{}", s),
+            Custom(e) => write!(f, "{}", e),
+            Context {
+                path,
+                offset,
+                source,
+            } => {
+                write!(f, "{}", path)?;
+                if let Some(offset) = offset {
+                    write!(f, " @ offset {}", offset)?;
+                }
+                write!(f, ": {}", source)
+            }
+        }
     }
 }
 
-impl error::Error for Error {
-    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
         match self {
+            #[cfg(feature = "std")]
             Io(e) => Some(e),
             Utf8(e) => Some(e),
+            Custom(e) => Some(e.as_ref()),
+            Context { source, .. } => Some(source.as_ref()),
             _ => None,
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Io(e)
@@ -62,3 +173,41 @@ impl From<&'static str> for Error {
         Other(s)
     }
 }
+
+///Receive the field with the path and offset in the error, #[derive(Rcv)] uses it for every field.
+#[doc(hidden)]
+#[inline]
+pub fn rcv_field<R, T, F>(reader: &mut R, path: &str, f: F) -> crate::Result<T>
+where
+    R: crate::Receiver + ?Sized,
+    F: FnOnce(&mut R) -> crate::Result<T>,
+{
+    let offset = reader.position();
+    f(reader).map_err(|e| e.in_field(path, offset))
+}
+
+///The error when the data end unexpectedly: io::ErrorKind::UnexpectedEof like Read
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn eof() -> Error {
+    Io(std::io::ErrorKind::UnexpectedEof.into())
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn eof() -> Error {
+    Eof
+}
+
+///Check the error of eof()
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn is_eof(e: &Error) -> bool {
+    matches!(e, Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof)
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+pub(crate) fn is_eof(e: &Error) -> bool {
+    matches!(e, Eof)
+}
@@ -0,0 +1,416 @@
+//! Length-prefixed frames: FramedSender writes the frame header + data, FramedReceiver reads exactly one frame.
+//!
+//! The header is u16, u32 or VLQ in either byte order (FrameFormat), the data use the byte order of the stream.
+
+use alloc::vec::Vec;
+
+use crate::endian::{ByteOrder, ByteOrdered, Length, OrderedRef, VLQ};
+use crate::error::Error;
+use crate::strict::Strict;
+use crate::tracked::Tracked;
+use crate::{EncodedLen, Receivable, Receiver, Result, Sendable, Sender};
+
+///The type of the frame length
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FrameHeader {
+    U16,
+    U32,
+    VLQ,
+}
+
+///Frame header format and the max frame length.
+/// The default is u32 frame length (not including the header) in the default byte order, 16 MiB at most.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FrameFormat {
+    pub header: FrameHeader,
+    ///Byte order of the u16/u32 header
+    pub order: ByteOrder,
+    ///The frame length includes the header itself
+    pub inclusive: bool,
+    ///Error::DataTooLarge if the data are longer, the MAX_LEN_* features are checked too
+    pub max_len: usize,
+}
+
+impl Default for FrameFormat {
+    fn default() -> Self {
+        Self {
+            header: FrameHeader::U32,
+            order: ByteOrder::DEFAULT,
+            inclusive: false,
+            max_len: Self::DEFAULT_MAX_LEN,
+        }
+    }
+}
+
+impl FrameFormat {
+    ///The default max_len: 16 MiB
+    pub const DEFAULT_MAX_LEN: usize = 16 * 1024 * 1024;
+
+    //the frame data are received in chunks, the buffer doesn't grow more than the data received
+    const CHUNK_LEN: usize = 64 * 1024;
+
+    ///The size of the header for the data length
+    #[inline]
+    fn header_len(&self, data_len: usize) -> usize {
+        match self.header {
+            FrameHeader::U16 => 2,
+            FrameHeader::U32 => 4,
+            //the header length is included in the VLQ value
+            FrameHeader::VLQ if self.inclusive => (1..)
+                .find(|n| VLQ(data_len + n).encoded_len() <= *n)
+                .unwrap_or(1),
+            FrameHeader::VLQ => VLQ(data_len).encoded_len(),
+        }
+    }
+
+    #[inline]
+    fn check(&self, data_len: usize) -> Result<()> {
+        if data_len > self.max_len {
+            return Err(Error::DataTooLarge {
+                len: data_len,
+                max: self.max_len,
+            });
+        }
+        Length(data_len).check()
+    }
+
+    fn snd_header<S: Sender>(&self, writer: &mut S, data_len: usize) -> Result<()> {
+        self.check(data_len)?;
+        let len = if self.inclusive {
+            data_len + self.header_len(data_len)
+        } else {
+            data_len
+        };
+
+        match self.header {
+            FrameHeader::U16 => {
+                let len = u16::try_from(len).map_err(|_| Error::DataTooLarge {
+                    len,
+                    max: u16::MAX as usize,
+                })?;
+                match self.order {
+                    ByteOrder::Little => writer.snd_all(&len.to_le_bytes()),
+                    ByteOrder::Big => writer.snd_all(&len.to_be_bytes()),
+                }
+            }
+            FrameHeader::U32 => {
+                let len = u32::try_from(len).map_err(|_| Error::DataTooLarge {
+                    len,
+                    max: u32::MAX as usize,
+                })?;
+                match self.order {
+                    ByteOrder::Little => writer.snd_all(&len.to_le_bytes()),
+                    ByteOrder::Big => writer.snd_all(&len.to_be_bytes()),
+                }
+            }
+            FrameHeader::VLQ => VLQ(len).snd_to(writer),
+        }
+    }
+
+    ///The data length of the frame
+    fn rcv_header<R: Receiver>(&self, reader: &mut R) -> Result<usize> {
+        let (len, header_len) = match self.header {
+            FrameHeader::U16 => {
+                let mut buf = [0; 2];
+                reader.rcv_all(&mut buf)?;
+                let len = match self.order {
+                    ByteOrder::Little => u16::from_le_bytes(buf),
+                    ByteOrder::Big => u16::from_be_bytes(buf),
+                };
+                (len as usize, 2)
+            }
+            FrameHeader::U32 => {
+                let mut buf = [0; 4];
+                reader.rcv_all(&mut buf)?;
+                let len = match self.order {
+                    ByteOrder::Little => u32::from_le_bytes(buf),
+                    ByteOrder::Big => u32::from_be_bytes(buf),
+                };
+                (len as usize, 4)
+            }
+            FrameHeader::VLQ => {
+                //the bytes received, the padded VLQ is longer than encoded_len() in lenient mode
+                let order = reader.byte_order();
+                let mut reader = Tracked::new(OrderedRef::new(reader, order));
+                let len = VLQ::rcv_from(&mut reader)?;
+                (*len, reader.offset())
+            }
+        };
+
+        let len = if self.inclusive {
+            len.checked_sub(header_len)
+                .ok_or(Error::Other("frame length is less than the header"))?
+        } else {
+            len
+        };
+        self.check(len)?;
+        Ok(len)
+    }
+}
+
+///Sender for frames, the data are serialized to a buffer first for the length.
+pub struct FramedSender<S> {
+    inner: S,
+    format: FrameFormat,
+    buf: Vec<u8>,
+}
+
+impl<S: Sender> FramedSender<S> {
+    pub fn new(inner: S, format: FrameFormat) -> Self {
+        Self {
+            inner,
+            format,
+            buf: Vec::new(),
+        }
+    }
+
+    ///Send the value in one frame
+    pub fn snd_frame<T: Sendable>(&mut self, v: T) -> Result<()> {
+        self.buf.clear();
+        let mut buf = ByteOrdered::new(core::mem::take(&mut self.buf), self.inner.byte_order());
+        let rst = v.snd_to(&mut buf);
+        self.buf = buf.into_inner();
+        rst?;
+
+        self.format.snd_header(&mut self.inner, self.buf.len())?;
+        self.inner.snd_all(&self.buf)
+    }
+
+    ///Send the bytes as one frame
+    pub fn snd_frame_bytes(&mut self, data: &[u8]) -> Result<()> {
+        self.format.snd_header(&mut self.inner, data.len())?;
+        self.inner.snd_all(data)
+    }
+
+    #[inline]
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+///Receiver for frames, the whole frame is consumed even if the value doesn't read all of it.
+pub struct FramedReceiver<R> {
+    inner: R,
+    format: FrameFormat,
+}
+
+impl<R: Receiver> FramedReceiver<R> {
+    pub fn new(inner: R, format: FrameFormat) -> Self {
+        Self { inner, format }
+    }
+
+    ///Receive the value from one frame, Error::Eof (io::ErrorKind::UnexpectedEof) if it needs more data.
+    /// The rest of the frame is ignored, Error::TrailingBytes in strict mode.
+    pub fn rcv_frame<T: Receivable>(&mut self) -> Result<T> {
+        let data = self.rcv_frame_bytes()?;
+        let mut reader = ByteOrdered::new(&data[..], self.inner.byte_order());
+        if !self.inner.is_strict() {
+            return T::rcv_from(&mut reader);
+        }
+
+        let mut reader = Strict::new(reader);
+        let v = T::rcv_from(&mut reader)?;
+        match reader.get_ref().get_ref().len() {
+            0 => Ok(v),
+            n => Err(Error::TrailingBytes(n)),
+        }
+    }
+
+    ///Receive the data of one frame.
+    /// The length is from the peer, so the data are received in chunks instead of allocating it at once.
+    pub fn rcv_frame_bytes(&mut self) -> Result<Vec<u8>> {
+        let len = self.format.rcv_header(&mut self.inner)?;
+        let mut data = Vec::with_capacity(len.min(FrameFormat::CHUNK_LEN));
+        while data.len() < len {
+            let start = data.len();
+            data.resize(start + (len - start).min(FrameFormat::CHUNK_LEN), 0);
+            self.inner.rcv_all(&mut data[start..])?;
+        }
+        Ok(data)
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::LE;
+    use crate::Snd;
+
+    fn round_trip(format: FrameFormat) -> Result<Vec<u8>> {
+        let mut w = FramedSender::new(Vec::new(), format);
+        w.snd_frame((1_u16, 2_u32))?;
+        w.snd_frame("frame")?;
+        let vec = w.into_inner();
+
+        let mut r = FramedReceiver::new(&vec[..], format);
+        //read less than the frame, it's an error in strict mode
+        if cfg!(feature = "strict") {
+            assert!(matches!(
+                r.rcv_frame::<u16>(),
+                Err(Error::TrailingBytes(4))
+            ));
+        } else {
+            assert_eq!(r.rcv_frame::<u16>()?, 1);
+        }
+        assert_eq!(r.rcv_frame::<alloc::string::String>()?, "frame");
+        assert!(r.get_ref().is_empty());
+        Ok(vec)
+    }
+
+    #[test]
+    fn test_frame_header() -> Result<()> {
+        let vec = round_trip(FrameFormat {
+            header: FrameHeader::U16,
+            order: ByteOrder::Big,
+            ..Default::default()
+        })?;
+        assert_eq!(vec[..2], [0, 6]);
+
+        let vec = round_trip(FrameFormat {
+            header: FrameHeader::U32,
+            order: ByteOrder::Little,
+            inclusive: true,
+            ..Default::default()
+        })?;
+        assert_eq!(vec[..4], [10, 0, 0, 0]);
+
+        let vec = round_trip(FrameFormat {
+            header: FrameHeader::VLQ,
+            inclusive: true,
+            ..Default::default()
+        })?;
+        assert_eq!(vec[0], 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_vlq_inclusive() -> Result<()> {
+        let format = FrameFormat {
+            header: FrameHeader::VLQ,
+            inclusive: true,
+            ..Default::default()
+        };
+        //127 bytes of data + 2 bytes of header
+        let data = [7_u8; 127];
+        let mut w = FramedSender::new(Vec::new(), format);
+        w.snd_frame_bytes(&data)?;
+        let vec = w.into_inner();
+        assert_eq!(vec.len(), 129);
+        assert_eq!(*VLQ::rcv_from(&mut &vec[..])?, 129);
+
+        let mut r = FramedReceiver::new(&vec[..], format);
+        assert_eq!(r.rcv_frame_bytes()?, data);
+
+        //the padded header is 2 bytes: 4 bytes of data
+        if !cfg!(feature = "strict") {
+            let vec = [0x80, 6, 1, 2, 3, 4];
+            let mut r = FramedReceiver::new(&vec[..], format);
+            assert_eq!(r.rcv_frame_bytes()?, [1, 2, 3, 4]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_limit() -> Result<()> {
+        let format = FrameFormat {
+            header: FrameHeader::U16,
+            max_len: 4,
+            ..Default::default()
+        };
+        let mut w = FramedSender::new(Vec::new(), format);
+        assert!(matches!(
+            w.snd_frame(1_u64),
+            Err(Error::DataTooLarge { len: 8, max: 4 })
+        ));
+        assert!(w.get_ref().is_empty());
+
+        let mut vec = Vec::new();
+        vec.snd(LE(8_u16))?;
+        vec.snd(1_u64)?;
+        let mut r = FramedReceiver::new(
+            &vec[..],
+            FrameFormat {
+                order: ByteOrder::Little,
+                ..format
+            },
+        );
+        assert!(matches!(
+            r.rcv_frame::<u64>(),
+            Err(Error::DataTooLarge { len: 8, max: 4 })
+        ));
+
+        //more data than the frame
+        let mut r = FramedReceiver::new(
+            &[2_u8, 0, 1, 0, 0, 0][..],
+            FrameFormat {
+                header: FrameHeader::U16,
+                order: ByteOrder::Little,
+                ..Default::default()
+            },
+        );
+        assert!(r.rcv_frame::<u32>().is_err());
+
+        //the default limit, the length from the peer isn't allocated
+        let mut r =
+            FramedReceiver::new(&[0xFF_u8, 0xFF, 0xFF, 0xFF, 1][..], FrameFormat::default());
+        assert!(matches!(
+            r.rcv_frame_bytes(),
+            Err(Error::DataTooLarge {
+                max: FrameFormat::DEFAULT_MAX_LEN,
+                ..
+            })
+        ));
+        let mut vec = Vec::new();
+        vec.snd(0x20_0000_u32)?;
+        vec.snd(1_u8)?;
+        let mut r = FramedReceiver::new(&vec[..], FrameFormat::default());
+        assert!(r.rcv_frame_bytes().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_strict() -> Result<()> {
+        let mut w = FramedSender::new(Vec::new(), FrameFormat::default());
+        w.snd_frame((1_u16, 2_u32))?;
+        w.snd_frame(3_u16)?;
+        let vec = w.into_inner();
+
+        let mut r = FramedReceiver::new(Strict::new(&vec[..]), FrameFormat::default());
+        assert!(matches!(
+            r.rcv_frame::<u16>(),
+            Err(Error::TrailingBytes(4))
+        ));
+        assert_eq!(r.rcv_frame::<u16>()?, 3);
+        Ok(())
+    }
+}
@@ -1,20 +1,58 @@
 //! Sender, Receiver, Snd, Rcv traits.
+//!
+//! no_std (core + alloc) without the default "std" feature.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "tokio")]
+pub mod async_io;
+pub mod borrow;
+pub mod bounded;
 pub mod endian;
 pub mod error;
+pub mod frame;
+pub mod size;
+pub mod strict;
+pub mod tracked;
 
-pub type Result<T> = std::result::Result<T, crate::error::Error>;
+#[cfg(feature = "tokio")]
+pub use async_io::{
+    AsyncFromReader, AsyncFromTag, AsyncRcv, AsyncReceivable, AsyncReceiver, AsyncSendable,
+    AsyncSender, AsyncSnd,
+};
+pub use borrow::{RcvRef, RefFromReader, RefReceivable, RefReceiver};
+pub use size::{EncodedLen, LenOf};
+use alloc::vec::Vec;
+use endian::ByteOrder;
+
+pub type Result<T> = core::result::Result<T, crate::error::Error>;
 
 ///Abstract layer for Write since it's not avaialbe in no_std
 pub trait Sender {
     fn snd_all(&mut self, buf: &[u8]) -> Result<()>;
 
     fn flush(&mut self) -> Result<()>;
+
+    ///Byte order for numbers, Length etc. The default is from the "little"/"big" feature.
+    /// Wrap the sender with endian::ByteOrdered to change it at runtime.
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        ByteOrder::DEFAULT
+    }
 }
 
 ///Abstract layer for Read since it's not avaialbe in no_std
 pub trait Receiver {
     fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()>;
 
+    ///Byte order for numbers, Length etc. The default is from the "little"/"big" feature.
+    /// Wrap the receiver with endian::ByteOrdered to change it at runtime.
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        ByteOrder::DEFAULT
+    }
+
     #[inline]
     #[allow(clippy::uninit_vec)]
     fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
@@ -27,6 +65,36 @@ pub trait Receiver {
         self.rcv_all(&mut vec)?;
         Ok(vec)
     }
+
+    ///Receive all the bytes left: the rest of the slice, #[size] (bounded::Bounded) or frame, the stream until the end.
+    /// The trailing Vec<u8> of the #[resend(other)] variant is received with it.
+    /// It only returns at the end of the stream, it blocks on an open socket: bound it with #[size] or a frame.
+    #[inline]
+    fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        let mut buf = [0];
+        loop {
+            match self.rcv_all(&mut buf) {
+                Ok(()) => vec.push(buf[0]),
+                Err(e) if error::is_eof(&e) => return Ok(vec),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    ///The byte offset of the next read for the error context, None if it's not tracked.
+    /// Wrap the receiver with tracked::Tracked to track it.
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        None
+    }
+
+    ///Reject the non-canonical data: bool other than 0/1, padded VLQ etc. The default is from the "strict" feature.
+    /// Wrap the receiver with strict::Strict to turn it on at runtime.
+    #[inline]
+    fn is_strict(&self) -> bool {
+        cfg!(feature = "strict")
+    }
 }
 
 ///Impl Sendable if the data need to be serialized.
@@ -70,6 +138,33 @@ pub trait FromReader: Sized {
 pub trait IntoWriter {
     #[allow(clippy::wrong_self_convention)]
     fn into_writer<S: Sender>(&self, writer: &mut S, len: usize) -> Result<()>;
+
+    ///The size of the data written with the len, EncodedLen for the #[len] attribute.
+    /// The bytes are counted without buffering by default.
+    #[inline]
+    fn encoded_len_with(&self, len: usize) -> usize {
+        let mut counter = size::Counter::default();
+        let _ = self.into_writer(&mut counter, len);
+        counter.0
+    }
+}
+
+///Receive Trait for the #[tag] attribute, #[derive(Rcv)] implements it for enums.
+/// The enum tag is from another field, for example: #[tag(header.kind)]
+pub trait FromTag: Sized {
+    type Tag;
+
+    fn from_tag<R: Receiver>(reader: &mut R, tag: Self::Tag) -> Result<Self>;
+}
+
+///Send Trait for the #[tag] attribute, #[derive(Snd)] implements it for enums.
+/// The enum is sent without the tag since it's in another field.
+pub trait IntoTag {
+    type Tag;
+
+    fn tag(&self) -> Self::Tag;
+
+    fn snd_untagged<S: Sender>(&self, writer: &mut S) -> Result<()>;
 }
 
 impl<S: Sender> Snd for S {
@@ -93,6 +188,7 @@ where
 }
 
 //impl Sender for all Write implementors
+#[cfg(feature = "std")]
 impl<W: std::io::Write + ?Sized> Sender for W {
     #[inline]
     fn snd_all(&mut self, buf: &[u8]) -> Result<()> {
@@ -107,10 +203,145 @@ impl<W: std::io::Write + ?Sized> Sender for W {
 }
 
 //impl Receiver for all Read implmentors
+#[cfg(feature = "std")]
 impl<R: std::io::Read + ?Sized> Receiver for R {
     #[inline]
     fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
         self.read_exact(buf)?;
         Ok(())
     }
+
+    #[inline]
+    fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        self.read_to_end(&mut vec)?;
+        Ok(vec)
+    }
+}
+
+//no_std: Vec<u8> and slices instead of Write/Read
+#[cfg(not(feature = "std"))]
+impl Sender for Vec<u8> {
+    #[inline]
+    fn snd_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+//the slice is advanced like Write for &mut [u8]
+#[cfg(not(feature = "std"))]
+impl Sender for &mut [u8] {
+    #[inline]
+    fn snd_all(&mut self, buf: &[u8]) -> Result<()> {
+        if buf.len() > self.len() {
+            return Err(error::eof());
+        }
+        let (a, b) = core::mem::take(self).split_at_mut(buf.len());
+        a.copy_from_slice(buf);
+        *self = b;
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<S: Sender + ?Sized> Sender for &mut S {
+    #[inline]
+    fn snd_all(&mut self, buf: &[u8]) -> Result<()> {
+        (**self).snd_all(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        (**self).byte_order()
+    }
+}
+
+//the slice is advanced like Read for &[u8]
+#[cfg(not(feature = "std"))]
+impl Receiver for &[u8] {
+    #[inline]
+    fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        if buf.len() > self.len() {
+            return Err(error::eof());
+        }
+        let (a, b) = self.split_at(buf.len());
+        buf.copy_from_slice(a);
+        *self = b;
+        Ok(())
+    }
+
+    #[inline]
+    fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        Ok(core::mem::take(self).to_vec())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<R: Receiver + ?Sized> Receiver for &mut R {
+    #[inline]
+    fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        (**self).rcv_all(buf)
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        (**self).byte_order()
+    }
+
+    #[inline]
+    fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        (**self).rcv_bytes(len)
+    }
+
+    #[inline]
+    fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        (**self).rcv_rest()
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        (**self).position()
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        (**self).is_strict()
+    }
+}
+
+#[cfg(all(test, not(feature = "std")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_std() -> Result<()> {
+        let mut buf = [0_u8; 6];
+        let mut w = &mut buf[..];
+        w.snd(1_u16)?;
+        w.snd(2_u32)?;
+        assert!(w.is_empty());
+        assert!(matches!(w.snd(3_u8), Err(error::Error::Eof)));
+
+        let mut r = &buf[..];
+        assert_eq!(r.rcv::<u16>()?, 1);
+        assert_eq!(r.rcv::<u32>()?, 2);
+        assert!(matches!(r.rcv::<u8>(), Err(error::Error::Eof)));
+        Ok(())
+    }
 }
@@ -0,0 +1,446 @@
+//! EncodedLen trait: the exact number of bytes of the serialized data, without serializing it.
+//!
+//! #[derive(EncodedLen)] implements it for structs and enums with the same attributes as Snd.
+//! LenOf is the length of the field for #[len_of]: the length #[len] reads back (bytes of strings, elements of Vec).
+
+use alloc::collections::{BTreeMap, LinkedList, VecDeque};
+use alloc::{borrow::Cow, boxed::Box, ffi::CString, string::String, vec::Vec};
+use core::num::{
+    NonZeroI16, NonZeroI32, NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroUsize,
+};
+use core::{ops::Range, time::Duration};
+#[cfg(feature = "std")]
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::endian::{Ascii, Length, SLeb128, ULeb128, UTF16Char, ZigZag, BE, LE, UTF16, VLQ};
+use crate::{Result, Sender};
+
+///The serialized size of Sendable, for example: write a length header before the data.
+pub trait EncodedLen {
+    fn encoded_len(&self) -> usize;
+}
+
+///Sender which counts the bytes only, IntoWriter::encoded_len_with uses it
+#[derive(Default)]
+pub(crate) struct Counter(pub(crate) usize);
+
+impl Sender for Counter {
+    #[inline]
+    fn snd_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.0 += buf.len();
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! fixed_len {
+    ($($t:ty: $n:expr),+ $(,)?) => {
+        $(
+            impl EncodedLen for $t {
+                #[inline]
+                fn encoded_len(&self) -> usize {
+                    $n
+                }
+            }
+        )+
+    };
+}
+
+fixed_len!(
+    u8: 1,
+    i8: 1,
+    bool: 1,
+    u16: 2,
+    i16: 2,
+    u32: 4,
+    i32: 4,
+    f32: 4,
+    char: 4,
+    u64: 8,
+    i64: 8,
+    f64: 8,
+    usize: 8,
+    isize: 8,
+    u128: 16,
+    i128: 16,
+    Duration: 12,
+    NonZeroU16: 2,
+    NonZeroI16: 2,
+    NonZeroU32: 4,
+    NonZeroI32: 4,
+    NonZeroU64: 8,
+    NonZeroUsize: 8,
+    NonZeroU128: 16,
+);
+
+impl<T: EncodedLen + ?Sized> EncodedLen for &T {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+}
+
+impl<T: EncodedLen> EncodedLen for LE<T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl<T: EncodedLen> EncodedLen for BE<T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+//u32 by default, u16, VLQ or ULeb128 with the "len_16"/"len_vlq"/"len_leb128" feature
+impl EncodedLen for Length {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        #[cfg(not(any(feature = "len_vlq", feature = "len_16", feature = "len_leb128")))]
+        let len = 4;
+
+        #[cfg(feature = "len_16")]
+        let len = 2;
+
+        #[cfg(feature = "len_vlq")]
+        let len = VLQ(self.0).encoded_len();
+
+        #[cfg(feature = "len_leb128")]
+        let len = ULeb128(self.0 as u64).encoded_len();
+
+        len
+    }
+}
+
+//7 bits per byte
+impl EncodedLen for VLQ {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        let bits = usize::BITS - self.0.leading_zeros();
+        (bits as usize).div_ceil(7).max(1)
+    }
+}
+
+impl EncodedLen for ULeb128 {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.encode().1
+    }
+}
+
+impl EncodedLen for SLeb128 {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.encode().1
+    }
+}
+
+impl EncodedLen for ZigZag<i16> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.to_leb128().encoded_len()
+    }
+}
+
+impl EncodedLen for ZigZag<i32> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.to_leb128().encoded_len()
+    }
+}
+
+impl EncodedLen for ZigZag<i64> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.to_leb128().encoded_len()
+    }
+}
+
+impl EncodedLen for str {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        Length(self.len()).encoded_len() + self.len()
+    }
+}
+
+impl EncodedLen for String {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.as_str().encoded_len()
+    }
+}
+
+impl EncodedLen for Ascii {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+
+impl EncodedLen for CString {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.as_bytes_with_nul().len()
+    }
+}
+
+impl EncodedLen for UTF16Char {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.0.len_utf16() * 2
+    }
+}
+
+impl EncodedLen for UTF16 {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        let len: usize = self.0.chars().map(|c| c.len_utf16() * 2).sum();
+        Length(len).encoded_len() + len
+    }
+}
+
+//the data only, no length header
+impl<T: EncodedLen, const N: usize> EncodedLen for [T; N] {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.iter().map(T::encoded_len).sum()
+    }
+}
+
+impl<T: EncodedLen> EncodedLen for [T] {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        Length(self.len()).encoded_len() + self.iter().map(T::encoded_len).sum::<usize>()
+    }
+}
+
+impl<T: EncodedLen> EncodedLen for Vec<T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.as_slice().encoded_len()
+    }
+}
+
+impl<T: EncodedLen> EncodedLen for VecDeque<T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        Length(self.len()).encoded_len() + self.iter().map(T::encoded_len).sum::<usize>()
+    }
+}
+
+impl<T: EncodedLen> EncodedLen for LinkedList<T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        Length(self.len()).encoded_len() + self.iter().map(T::encoded_len).sum::<usize>()
+    }
+}
+
+//bool header + data
+impl<T: EncodedLen> EncodedLen for Option<T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        1 + self.as_ref().map_or(0, T::encoded_len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: EncodedLen, V: EncodedLen> EncodedLen for HashMap<K, V> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        Length(self.len()).encoded_len()
+            + self
+                .iter()
+                .map(|(k, v)| k.encoded_len() + v.encoded_len())
+                .sum::<usize>()
+    }
+}
+
+impl<K: EncodedLen, V: EncodedLen> EncodedLen for BTreeMap<K, V> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        Length(self.len()).encoded_len()
+            + self
+                .iter()
+                .map(|(k, v)| k.encoded_len() + v.encoded_len())
+                .sum::<usize>()
+    }
+}
+
+impl<T: EncodedLen + ?Sized> EncodedLen for Box<T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+}
+
+impl<T: EncodedLen + Clone> EncodedLen for Cow<'_, T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+}
+
+impl<T: EncodedLen> EncodedLen for Range<T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.start.encoded_len() + self.end.encoded_len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl EncodedLen for PathBuf {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.to_string_lossy().encoded_len()
+    }
+}
+
+macro_rules! len_tuple {
+    ($($name:ident), +) => {
+        impl<$($name: EncodedLen),+> EncodedLen for ($($name,)+) {
+            #[allow(non_snake_case)]
+            #[inline]
+            fn encoded_len(&self) -> usize {
+                let ($($name,)+) = self;
+                0 $(+ $name.encoded_len())+
+            }
+        }
+    };
+}
+
+len_tuple!(A);
+len_tuple!(A, B);
+len_tuple!(A, B, C);
+len_tuple!(A, B, C, D);
+len_tuple!(A, B, C, D, E);
+len_tuple!(A, B, C, D, E, F);
+len_tuple!(A, B, C, D, E, F, G);
+len_tuple!(A, B, C, D, E, F, G, H);
+len_tuple!(A, B, C, D, E, F, G, H, I);
+len_tuple!(A, B, C, D, E, F, G, H, I, J);
+len_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+len_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+///The length for the #[len_of(field)] attribute: the length #[len] reads back, without the padding.
+/// For example: the bytes of UTF16 (2 or 4 bytes per char), the number of elements of Vec.
+pub trait LenOf {
+    fn len_of(&self) -> usize;
+}
+
+impl LenOf for str {
+    #[inline]
+    fn len_of(&self) -> usize {
+        self.len()
+    }
+}
+
+impl LenOf for String {
+    #[inline]
+    fn len_of(&self) -> usize {
+        self.len()
+    }
+}
+
+impl LenOf for Ascii {
+    #[inline]
+    fn len_of(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl LenOf for UTF16 {
+    #[inline]
+    fn len_of(&self) -> usize {
+        self.0.chars().map(|c| c.len_utf16() * 2).sum()
+    }
+}
+
+impl<T> LenOf for [T] {
+    #[inline]
+    fn len_of(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> LenOf for Vec<T> {
+    #[inline]
+    fn len_of(&self) -> usize {
+        self.as_slice().len_of()
+    }
+}
+
+impl<T: LenOf + ?Sized> LenOf for &T {
+    #[inline]
+    fn len_of(&self) -> usize {
+        (**self).len_of()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IntoWriter, Sendable};
+
+    fn check<T: Sendable + EncodedLen>(v: T) -> Result<()> {
+        let mut vec = Vec::new();
+        v.snd_to(&mut vec)?;
+        assert_eq!(v.encoded_len(), vec.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoded_len() -> Result<()> {
+        check(1_u8)?;
+        check(LE(2_u32))?;
+        check(BE(3_i128))?;
+        check(Duration::from_secs(3))?;
+        check('c')?;
+        check(Length(300))?;
+        for v in [0, 127, 128, 16383, 16384, usize::MAX] {
+            check(VLQ(v))?;
+        }
+        check("hello")?;
+        check(String::from("hi"))?;
+        check(Ascii("ascii".into()))?;
+        check(CString::new("abc").unwrap())?;
+        check(UTF16Char('𝌆'))?;
+        check(UTF16("2欢迎2𝌆𠮷".into()))?;
+        check([1_u16, 2, 3])?;
+        check(vec![String::from("a"), String::from("bc")])?;
+        check(Some(5_u16))?;
+        check(None::<u16>)?;
+        check(BTreeMap::from([(1_u8, String::from("one"))]))?;
+        check(Box::new(7_u64))?;
+        check((1_u8, 2_u16, String::from("three")))?;
+        check(1_u32..5)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoded_len_with() {
+        assert_eq!(String::from("abc").encoded_len_with(8), 8);
+        assert_eq!(String::from("abc").encoded_len_with(2), 2);
+        assert_eq!(UTF16("ab".into()).encoded_len_with(10), 10);
+        assert_eq!(vec![1_u32, 2].encoded_len_with(3), 12);
+    }
+
+    #[test]
+    fn test_len_of() {
+        assert_eq!("abc".len_of(), 3);
+        assert_eq!(Ascii("ab".into()).len_of(), 2);
+        //the same bytes as IntoWriter with the natural length
+        let s = UTF16("2欢迎2𝌆".into());
+        assert_eq!(s.len_of(), 12);
+        assert_eq!(s.encoded_len_with(s.len_of()), s.len_of());
+        //elements, the same as FromReader for Vec
+        let v = vec![1_u32, 2];
+        assert_eq!(v.len_of(), 2);
+        assert_eq!(v.encoded_len_with(v.len_of()), 8);
+    }
+}
@@ -0,0 +1,123 @@
+//! Strict receiver which rejects the non-canonical data, so every accepted message has exactly one encoding.
+//!
+//! In strict mode: bool must be 0 or 1, VLQ/LEB128 have no padding bytes,
+//! Ascii has no bytes above 127, and a frame has no bytes left after the value.
+//! The "strict" feature makes it the default for all receivers.
+
+use alloc::vec::Vec;
+
+use crate::borrow::RefReceiver;
+use crate::endian::ByteOrder;
+use crate::{Receiver, Result};
+
+///Receiver which turns on the strict mode for the inner receiver.
+pub struct Strict<R> {
+    inner: R,
+}
+
+impl<R> Strict<R> {
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Receiver> Receiver for Strict<R> {
+    #[inline]
+    fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.inner.rcv_all(buf)
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.inner.byte_order()
+    }
+
+    #[inline]
+    fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        self.inner.rcv_bytes(len)
+    }
+
+    #[inline]
+    fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        self.inner.rcv_rest()
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        self.inner.position()
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        true
+    }
+}
+
+impl<'a, R: RefReceiver<'a>> RefReceiver<'a> for Strict<R> {
+    #[inline]
+    fn rcv_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        self.inner.rcv_slice(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::{Ascii, VLQ};
+    use crate::error::Error;
+    use crate::Rcv;
+
+    #[test]
+    fn test_strict() -> Result<()> {
+        let data = [2_u8, 0x80, 0x81, 0x00, 1, 0xC8];
+        //lenient by default
+        #[cfg(not(feature = "strict"))]
+        {
+            let mut r = &data[..];
+            assert!(r.rcv::<bool>()?);
+            assert_eq!(*r.rcv::<VLQ>()?, 128);
+        }
+
+        let mut r = Strict::new(&data[..]);
+        assert!(matches!(r.rcv::<bool>(), Err(Error::InvalidBool(2))));
+        assert!(matches!(r.rcv::<VLQ>(), Err(Error::NonCanonical(_))));
+
+        let mut r = Strict::new(&data[3..]);
+        assert!(!r.rcv::<bool>()?);
+        assert!(r.rcv::<bool>()?);
+        assert!(r.get_ref().len() == 1);
+
+        //usize overflow, an error without strict mode too
+        let data = [0xFF_u8; 11];
+        let mut r = Strict::new(&data[..]);
+        assert!(matches!(r.rcv::<VLQ>(), Err(Error::Overflow(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict_ascii() -> Result<()> {
+        let data = [0xC8_u8, b'a'];
+        assert!(Ascii::from_bytes(&data, false).is_ok());
+        assert!(matches!(
+            Ascii::from_bytes(&data, true),
+            Err(Error::InvalidAscii(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! Tracked receiver which counts the bytes received, the offset is added to the decoding errors.
+//!
+//! #[derive(Rcv)] reports the field path with Receiver::position() in Error::Context, for example:
+//! "IoPrinter.device.dos_name @ offset 12".
+
+use alloc::vec::Vec;
+
+use crate::borrow::RefReceiver;
+use crate::endian::ByteOrder;
+use crate::{Receiver, Result};
+
+///Receiver which tracks the byte offset from where it's created.
+pub struct Tracked<R> {
+    inner: R,
+    pos: usize,
+}
+
+impl<R> Tracked<R> {
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self { inner, pos: 0 }
+    }
+
+    ///The bytes received so far
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    #[inline]
+    pub(crate) fn advance(&mut self, len: usize) {
+        self.pos += len;
+    }
+}
+
+impl<R: Receiver> Receiver for Tracked<R> {
+    #[inline]
+    fn rcv_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.inner.rcv_all(buf)?;
+        self.advance(buf.len());
+        Ok(())
+    }
+
+    #[inline]
+    fn byte_order(&self) -> ByteOrder {
+        self.inner.byte_order()
+    }
+
+    #[inline]
+    fn rcv_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        let vec = self.inner.rcv_bytes(len)?;
+        self.advance(len);
+        Ok(vec)
+    }
+
+    #[inline]
+    fn rcv_rest(&mut self) -> Result<Vec<u8>> {
+        let vec = self.inner.rcv_rest()?;
+        self.advance(vec.len());
+        Ok(vec)
+    }
+
+    #[inline]
+    fn position(&self) -> Option<usize> {
+        Some(self.pos)
+    }
+
+    #[inline]
+    fn is_strict(&self) -> bool {
+        self.inner.is_strict()
+    }
+}
+
+impl<'a, R: RefReceiver<'a>> RefReceiver<'a> for Tracked<R> {
+    #[inline]
+    fn rcv_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        let data = self.inner.rcv_slice(len)?;
+        self.advance(len);
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::{Rcv, RcvRef, Snd};
+
+    #[test]
+    fn test_tracked() -> Result<()> {
+        let mut vec = Vec::new();
+        vec.snd(1_u16)?;
+        vec.snd("abc")?;
+
+        let mut r = Tracked::new(&vec[..]);
+        assert_eq!(r.position(), Some(0));
+        assert_eq!(r.rcv::<u16>()?, 1);
+        assert_eq!(r.offset(), 2);
+        assert_eq!(r.rcv_ref::<&str>()?, "abc");
+        assert_eq!(r.position(), Some(vec.len()));
+        //the failed read is not counted
+        assert!(r.rcv::<u8>().is_err());
+        assert_eq!(r.offset(), vec.len());
+        assert!(r.get_ref().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_context() {
+        let e = Error::Zero.in_field("Inner.value", Some(4));
+        assert_eq!(
+            e.to_string(),
+            "Inner.value @ offset 4: zero value for the non-zero integer"
+        );
+        //the outer field replaces the type name of the inner path, the offset of the inner field is kept
+        let e = e.in_field("Outer.inner", Some(2));
+        assert_eq!(
+            e.to_string(),
+            "Outer.inner.value @ offset 4: zero value for the non-zero integer"
+        );
+        assert!(matches!(e.root(), Error::Zero));
+        assert!(matches!(
+            core::error::Error::source(&e).and_then(|e| e.downcast_ref::<Error>()),
+            Some(Error::Zero)
+        ));
+
+        let e = Error::InvalidTag { ty: "Kind", tag: 3 }.in_field("Pdu::Data.1", None);
+        assert_eq!(e.to_string(), "Pdu::Data.1: invalid tag 3 for Kind");
+    }
+}
@@ -0,0 +1,330 @@
+#![cfg(feature = "tokio")]
+
+use resend::{
+    endian::{Ascii, BE, LE, UTF16, VLQ},
+    error::Error,
+    strict::Strict,
+    tracked::Tracked,
+    AsyncRcv, AsyncReceiver, AsyncSnd,
+};
+use resend_derive::{Rcv, Snd};
+
+#[derive(Snd, Rcv, PartialEq, Debug)]
+#[repr(u16)]
+enum Color {
+    Red = 2,
+    Blue = 32,
+}
+
+#[derive(Snd, Rcv, PartialEq, Debug)]
+struct Point {
+    x: u16,
+    y: u16,
+    #[len(x)]
+    s: String,
+    #[len(y)]
+    u: UTF16,
+}
+
+#[derive(Snd, Rcv, PartialEq, Debug)]
+struct Person {
+    name: String,
+    age: u16,
+    #[when(age > 30)]
+    senior: Option<bool>,
+    #[skip]
+    ignore: u32,
+    color: Color,
+    point: Point,
+}
+
+#[repr(u32)]
+#[derive(Snd, Rcv, Debug, PartialEq)]
+enum DeviceType {
+    A,
+    Pt(Point) = 22,
+}
+
+#[derive(Snd, Rcv, Debug, PartialEq)]
+enum Versioned {
+    V1(u16),
+    #[resend(other)]
+    Unknown(u8, String),
+}
+
+#[derive(Snd, Rcv, Debug, PartialEq)]
+struct Tagged {
+    kind: u8,
+    name: String,
+    #[tag(kind, check)]
+    value: Versioned,
+}
+
+#[derive(Snd, Rcv, Debug, PartialEq)]
+struct SizedColor {
+    #[size(8)]
+    color: Color,
+    tail: u16,
+}
+
+#[derive(Snd, Rcv, Debug, PartialEq)]
+struct Delta {
+    #[varint]
+    dx: i32,
+    #[varint]
+    #[len(3)]
+    dy: i64,
+}
+
+#[tokio::test]
+async fn test_async_varint() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let d = Delta { dx: -65, dy: 300 };
+    client.snd(&d).await?;
+    assert_eq!(server.rcv::<Delta>().await?, d);
+    Ok(())
+}
+
+#[derive(Snd, Rcv, Debug, PartialEq)]
+#[resend(little)]
+struct Sample {
+    channel: u8,
+    value: i32,
+    #[be]
+    stamp: u64,
+}
+
+#[tokio::test]
+async fn test_async_byte_order() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let s = Sample {
+        channel: 3,
+        value: -2,
+        stamp: 0x0102,
+    };
+    client.snd(&s).await?;
+    client.snd(0_u8).await?;
+    let mut buf = [0; 14];
+    server.rcv_all(&mut buf).await?;
+    assert_eq!(buf, [3, 0xFE, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 1, 2, 0]);
+
+    client.snd(&s).await?;
+    assert_eq!(server.rcv::<Sample>().await?, s);
+
+    client.snd(BE(vec![1_u16, 2])).await?;
+    client.snd(LE(&s)).await?;
+    let v: BE<Vec<u16>> = server.rcv().await?;
+    assert_eq!(v.0, [1, 2]);
+    assert_eq!(server.rcv::<LE<Sample>>().await?.0, s);
+    Ok(())
+}
+
+#[derive(Snd, Rcv, Debug, PartialEq)]
+struct Status {
+    #[bits(1)]
+    ready: bool,
+    #[bits(7)]
+    code: u8,
+    #[bits(12)]
+    id: u16,
+}
+
+#[tokio::test]
+async fn test_async_bits() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let s = Status {
+        ready: true,
+        code: 0x7F,
+        id: 0xFFF,
+    };
+    client.snd(&s).await?;
+    assert_eq!(server.rcv::<Status>().await?, s);
+    Ok(())
+}
+
+#[derive(Snd, Rcv, Debug, PartialEq)]
+enum Reply<T> {
+    Ok(T),
+    Err(u16),
+}
+
+#[tokio::test]
+async fn test_async_generics() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let replies = [Reply::Ok(Color::Blue), Reply::Err(3)];
+    client.snd(&replies).await?;
+    assert_eq!(server.rcv::<[Reply<Color>; 2]>().await?, replies);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_size() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let s = SizedColor {
+        color: Color::Blue,
+        tail: 7,
+    };
+    client.snd(&s).await?;
+
+    let s1: SizedColor = server.rcv().await?;
+    assert_eq!(s, s1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_context() -> resend::Result<()> {
+    let (mut client, server) = tokio::io::duplex(64);
+    //Point.s is 3 bytes but only 2 are sent
+    client.snd(3_u16).await?;
+    client.snd(0_u16).await?;
+    client.snd(*b"ab").await?;
+    drop(client);
+
+    let mut server = Tracked::new(server);
+    let e = server.rcv::<Point>().await.unwrap_err();
+    assert!(matches!(
+        &e,
+        Error::Context { path, offset: Some(4), .. } if path == "Point.s"
+    ));
+    Ok(())
+}
+
+#[derive(Snd, Rcv, PartialEq, Debug)]
+struct Label {
+    n: u8,
+    #[len(n)]
+    name: Ascii,
+}
+
+#[tokio::test]
+async fn test_async_strict() -> resend::Result<()> {
+    let (mut client, server) = tokio::io::duplex(64);
+    client.snd(*b"\x01\x02\x80\x01").await?;
+    drop(client);
+
+    let mut server = Strict::new(server);
+    assert!(server.rcv::<bool>().await?);
+    assert!(matches!(
+        server.rcv::<bool>().await,
+        Err(Error::InvalidBool(2))
+    ));
+    assert!(matches!(
+        server.rcv::<VLQ>().await,
+        Err(Error::NonCanonical(_))
+    ));
+
+    //#[len] fields are decoded from the bytes with the strictness of the reader
+    let (mut client, server) = tokio::io::duplex(64);
+    client.snd(*b"\x02\xC3\xA9").await?;
+    drop(client);
+    assert!(matches!(
+        Strict::new(server).rcv::<Label>().await,
+        Err(Error::Context { path, source, .. })
+            if path == "Label.name" && matches!(*source, Error::InvalidAscii(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_tag() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let t = Tagged {
+        kind: 0,
+        name: "v1".to_string(),
+        value: Versioned::V1(5),
+    };
+    client.snd(&t).await?;
+
+    let t1: Tagged = server.rcv().await?;
+    assert_eq!(t, t1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_other() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    client
+        .snd(&Versioned::Unknown(8, "new".to_string()))
+        .await?;
+    client.snd(&Versioned::V1(2)).await?;
+
+    let v: Versioned = server.rcv().await?;
+    assert_eq!(v, Versioned::Unknown(8, "new".to_string()));
+    let v: Versioned = server.rcv().await?;
+    assert_eq!(v, Versioned::V1(2));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_person() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(16);
+    let p = Person {
+        name: "Great".to_string(),
+        age: 32,
+        senior: Some(true),
+        ignore: 0,
+        color: Color::Blue,
+        point: Point {
+            x: 5,
+            y: 12,
+            s: "great".to_string(),
+            u: UTF16("12345".to_string()),
+        },
+    };
+
+    let (sent, received) = tokio::join!(
+        async {
+            client.snd(&p).await?;
+            client.snd(&DeviceType::A).await?;
+            client.snd(&Color::Red).await
+        },
+        async {
+            let p: Person = server.rcv().await?;
+            let dt: DeviceType = server.rcv().await?;
+            let c: Color = server.rcv().await?;
+            resend::Result::Ok((p, dt, c))
+        }
+    );
+    sent?;
+    let (p1, dt, c) = received?;
+
+    assert_eq!(p, p1);
+    assert_eq!(dt, DeviceType::A);
+    assert_eq!(c, Color::Red);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_enum() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let p = Point {
+        x: 5,
+        y: 8,
+        s: "1234".to_string(),
+        u: UTF16("123".to_string()),
+    };
+    client.snd(&DeviceType::Pt(p)).await?;
+
+    let dt: DeviceType = server.rcv().await?;
+    assert_eq!(
+        dt,
+        DeviceType::Pt(Point {
+            x: 5,
+            y: 8,
+            s: "1234".to_string(),
+            u: UTF16("123".to_string())
+        })
+    );
+    Ok(())
+}
+
+//the futures are Send for the concrete types, so they can be spawned
+#[tokio::test]
+async fn test_async_spawn() -> resend::Result<()> {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let task = tokio::spawn(async move { server.rcv::<Delta>().await });
+    let d = Delta { dx: 5, dy: -7 };
+    client.snd(&d).await?;
+    assert_eq!(task.await.unwrap()?, d);
+    Ok(())
+}
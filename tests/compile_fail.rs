@@ -0,0 +1,6 @@
+//misuse of the derive is reported at the attribute or the field
+#[test]
+fn test_compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -1,13 +1,17 @@
-#![cfg(any(feature = "little", feature = "big"))]
 use std::assert_eq;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::marker::PhantomData;
 
 use resend::{
-    endian::{UTF16, VLQ},
-    Rcv, Receivable, Sendable, Snd,
+    endian::{ByteOrder, ByteOrdered, BE, LE, UTF16, VLQ},
+    error::Error,
+    tracked::Tracked,
+    EncodedLen, Rcv, RcvRef, Receivable, Sendable, Snd,
 };
-use resend_derive::{Rcv, Snd};
+use resend_derive::{EncodedLen, Rcv, Snd};
 
-#[derive(Copy, Clone, Debug, Snd, Rcv, PartialEq)]
+#[derive(Copy, Clone, Debug, Snd, Rcv, EncodedLen, PartialEq)]
 #[repr(u16)]
 enum Color {
     Red = 2,
@@ -15,7 +19,7 @@ enum Color {
     Green = 4,
 }
 
-#[derive(Snd, Rcv, PartialEq, Debug)]
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
 pub struct Point {
     x: u16,
     y: u16,
@@ -25,7 +29,7 @@ pub struct Point {
     u: UTF16,
 }
 
-#[derive(Snd, Rcv, PartialEq, Debug)]
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
 struct Person {
     name: String,
     age: u16,
@@ -42,7 +46,7 @@ struct Person {
 //discriminants on non-unit variants are experimental
 //https://github.com/rust-lang/rust/issues/60553
 #[repr(u32)]
-#[derive(Snd, Rcv, Debug, PartialEq)]
+#[derive(Snd, Rcv, EncodedLen, Debug, PartialEq)]
 // #[cfg(feature = "unstable")]//no need since 1.66
 enum DeviceType {
     A,
@@ -50,6 +54,394 @@ enum DeviceType {
     Pt(Point) = 22,
 }
 
+#[derive(Snd, Rcv, EncodedLen, Debug, PartialEq)]
+struct Slot {
+    id: u8,
+    device: DeviceType,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct ChannelId(u16);
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Channel(
+    ChannelId,
+    u16,
+    #[len(_1)] String,
+    #[when(_1 > 3)] Option<u32>,
+    #[skip] u32,
+);
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Empty;
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[repr(u8)]
+enum Pdu {
+    Data {
+        channel: u16,
+        payload: Vec<u8>,
+    } = 1,
+    Ack(u32, u32),
+    Name {
+        len: u16,
+        #[len(len)]
+        name: String,
+        #[when(len > 2)]
+        extra: Option<u16>,
+        #[skip]
+        cached: u32,
+    },
+    Padded(u8, #[len(_0)] Vec<u16>),
+    Close,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[repr(u64)]
+enum Wide {
+    Small = 1,
+    Big = 0x1_0000_0001,
+    Next(u8),
+}
+
+const BASE: i16 = -300;
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[repr(i16)]
+enum Signed {
+    Neg = -2,
+    Zero,
+    Expr(u8) = BASE * 2,
+    Shift = 1 << 8,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[repr(C, i8)]
+enum Tiny {
+    A = -128,
+    B(u8),
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[repr(usize)]
+enum Pointer {
+    A = 7,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[repr(u32)]
+enum Message {
+    Hello(u16) = 1,
+    Bye = 2,
+    #[resend(other)]
+    Unknown(u32),
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[repr(u16)]
+enum Body {
+    Ping = 1,
+    #[resend(other)]
+    Unknown(u16, Vec<u8>),
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct PduHeader {
+    kind: u16,
+    len: u16,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct TaggedPdu {
+    header: PduHeader,
+    flags: u8,
+    #[tag(header.kind, check)]
+    body: Body,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct SizedBody {
+    size: u8,
+    #[size(size)]
+    body: Body,
+    tail: u8,
+}
+
+//the later fields have the names used in the generated code
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+enum Setting {
+    Set {
+        kind: u8,
+        #[when(kind == 1)]
+        extra: Option<u16>,
+        condition: u16,
+    },
+    Marked {
+        tag: u8,
+    },
+    Tagged {
+        kind: u8,
+        #[tag(kind, check)]
+        msg: Message,
+        tag: u8,
+    },
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct TaggedTuple(u8, #[tag(_0)] Message);
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Name<'a> {
+    first: &'a str,
+    #[len(8)]
+    last: &'a str,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[resend(borrow)]
+struct Named<'a> {
+    id: u8,
+    name: Name<'a>,
+}
+
+//no field borrows: Receivable like the other structs
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Shared<'a> {
+    value: Cow<'a, u32>,
+    #[skip]
+    marker: PhantomData<&'a u8>,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Frame<'a> {
+    len: u16,
+    name: Name<'a>,
+    #[len(len)]
+    data: &'a [u8],
+    color: Color,
+    #[when(len > 2)]
+    checksum: Option<u32>,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct SizedPdu {
+    size: u16,
+    #[size(size)]
+    header: PduHeader,
+    #[size(6)]
+    name: String,
+    tail: u8,
+}
+
+const ID_LEN: usize = 3;
+
+//the lengths are computed from the fields before them
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Record {
+    cb_name: u16,
+    flags: u8,
+    //cb_name counts the null terminator
+    #[len(cb_name - 2)]
+    name: String,
+    #[len((flags >> 4) & 0xF)]
+    items: Vec<u16>,
+    #[size(usize::from(flags & 0xF) * 2)]
+    header: PduHeader,
+    #[len(ID_LEN + 1)]
+    id: String,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Sample(u8, #[len(_0 as usize * 2)] Vec<u8>);
+
+//the length fields are computed when it's sent
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Printer {
+    #[len_of(pnp_name)]
+    #[le]
+    pnp_name_len: u32,
+    #[count_of(ids)]
+    #[be]
+    id_count: u16,
+    #[len_of(header)]
+    header_len: u8,
+    #[len(pnp_name_len)]
+    pnp_name: UTF16,
+    #[len(id_count)]
+    ids: Vec<u16>,
+    #[size(header_len)]
+    header: PduHeader,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Items {
+    #[len_of(items)]
+    #[le]
+    n: u16,
+    #[len(n)]
+    items: Vec<u16>,
+}
+
+//the lengths of the fields as they are sent
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Extras {
+    flag: u8,
+    #[len_of(extra)]
+    extra_len: u8,
+    #[len_of(v)]
+    v_len: u8,
+    #[when(flag == 1)]
+    extra: Option<u32>,
+    #[varint]
+    v: i32,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct TaggedLen {
+    kind: u16,
+    #[len_of(body)]
+    body_len: u8,
+    #[tag(kind)]
+    body: Body,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+enum Chunk {
+    Data(#[count_of(_1)] u8, #[len(_0)] Vec<u8>),
+    End,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Delta {
+    #[varint]
+    dx: i32,
+    #[varint]
+    dy: i64,
+    #[varint]
+    #[len(4)]
+    dz: i16,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+enum Move {
+    Step(#[varint] i32),
+    Jump(
+        u8,
+        #[varint]
+        #[len(_0)]
+        i64,
+    ),
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Mixed {
+    kind: u8,
+    #[be]
+    id: u32,
+    #[le]
+    crc: u16,
+    count: u16,
+    #[be]
+    #[when(kind == 1)]
+    extra: Option<u16>,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[resend(big)]
+struct NetHeader {
+    version: u8,
+    urgent: bool,
+    len: u32,
+    #[le]
+    checksum: u16,
+    name: UTF16,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[resend(big)]
+#[repr(u16)]
+enum Op {
+    Read(u32) = 1,
+    Write {
+        addr: u16,
+        #[le]
+        value: u32,
+    } = 2,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[resend(big)]
+struct IpFragment {
+    #[bits(3)]
+    flags: u8,
+    #[bits(13)]
+    offset: u16,
+    ttl: u8,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Deltas {
+    #[bits(4)]
+    dx: i8,
+    #[bits(12)]
+    dy: i16,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[resend(lsb_first)]
+struct Flags(#[bits(1)] bool, #[bits(2)] u8, #[bits(1)] bool, u8);
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+enum Packed {
+    Nibbles {
+        #[bits(4)]
+        hi: u8,
+        #[bits(4)]
+        lo: u8,
+    },
+    Wide(
+        #[be]
+        #[bits(20)]
+        u32,
+    ),
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Envelope<T> {
+    id: u32,
+    body: T,
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Block<const N: usize> {
+    kind: u8,
+    data: [u8; N],
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+enum Reply<T, E>
+where
+    T: Clone,
+{
+    Ok(T),
+    Err { code: u16, detail: E },
+}
+
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+struct Labeled<'a, T> {
+    label: &'a str,
+    value: T,
+}
+
+//the marker isn't sent, so T doesn't need any bound
+#[derive(Snd, Rcv, EncodedLen, PartialEq, Debug)]
+#[resend(bound = "")]
+struct Typed<T> {
+    raw: u32,
+    #[skip]
+    marker: PhantomData<T>,
+}
+
 pub struct VarLenString(pub String);
 
 impl Sendable for VarLenString {
@@ -92,6 +484,55 @@ fn test_point() -> resend::Result<()> {
 }
 
 #[test]
+fn test_point_byte_order() -> resend::Result<()> {
+    let p = Point {
+        x: 0x0102,
+        y: 4,
+        s: "12".to_string(),
+        u: UTF16("3".to_string()),
+    };
+
+    let mut big = Vec::new();
+    ByteOrdered::big(&mut big).snd(&p)?;
+    assert_eq!(&big[..4], &[1, 2, 0, 4]);
+
+    let mut little = Vec::new();
+    ByteOrdered::little(&mut little).snd(&p)?;
+    assert_eq!(&little[..4], &[2, 1, 4, 0]);
+
+    let p1: Point = ByteOrdered::big(&big[..]).rcv()?;
+    assert_eq!(p, p1);
+    let p1: Point = ByteOrdered::little(&little[..]).rcv()?;
+    assert_eq!(p, p1);
+    Ok(())
+}
+
+#[test]
+fn test_point_wrapped() -> resend::Result<()> {
+    let p = Point {
+        x: 0x0102,
+        y: 4,
+        s: "12".to_string(),
+        u: UTF16("3".to_string()),
+    };
+
+    let mut vec = Vec::new();
+    vec.snd(BE(&p))?;
+    vec.snd(LE(&p))?;
+    let mut big = Vec::new();
+    ByteOrdered::big(&mut big).snd(&p)?;
+    let mut little = Vec::new();
+    ByteOrdered::little(&mut little).snd(&p)?;
+    assert_eq!(vec, [big, little].concat());
+
+    let (p1, p2): (BE<Point>, LE<Point>) = vec.as_slice().rcv()?;
+    assert_eq!(p1.0, p);
+    assert_eq!(p2.0, p);
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_person() -> resend::Result<()> {
     let mut vec = Vec::new();
     let p = Person {
@@ -137,6 +578,7 @@ fn test_person() -> resend::Result<()> {
 
 // #[cfg(feature = "unstable")]//no need since 1.66
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_enum() -> resend::Result<()> {
     assert_eq!(32, Color::Blue as u32);
     let mut vec = Vec::new();
@@ -170,15 +612,27 @@ fn test_enum() -> resend::Result<()> {
         })
     );
 
+    //owned values encode the same as references
+    let mut owned = Vec::new();
+    owned.snd(Color::Blue)?;
+    owned.snd(DeviceType::B)?;
+    owned.snd(dt)?;
+    assert_eq!(owned, vec);
+
     Ok(())
 }
 
 #[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn test_unit_enum() -> resend::Result<()> {
     assert_eq!(32, Color::Blue as u32);
     let mut vec = Vec::new();
     vec.snd(&Color::Blue)?;
 
+    let mut owned = Vec::new();
+    owned.snd(Color::Blue)?;
+    assert_eq!(owned, vec);
+
     let mut buf = &vec[..];
     let c: Color = buf.rcv()?;
     assert_eq!(c, Color::Blue);
@@ -197,3 +651,942 @@ fn test_unit_enum() -> resend::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_tuple_struct() -> resend::Result<()> {
+    let mut vec = Vec::new();
+    vec.snd(ChannelId(1003))?;
+    vec.snd(&Empty)?;
+    assert_eq!(vec.len(), 2);
+
+    let c = Channel(ChannelId(7), 5, "12".to_string(), Some(9), 33);
+    vec.snd(&c)?;
+    let c2 = Channel(ChannelId(8), 2, "12".to_string(), None, 0);
+    vec.snd(&c2)?;
+
+    let mut buf = &vec[..];
+    let id: ChannelId = buf.rcv()?;
+    assert_eq!(id, ChannelId(1003));
+    let e: Empty = buf.rcv()?;
+    assert_eq!(e, Empty);
+
+    let mut c1: Channel = buf.rcv()?;
+    assert_eq!(c1.2, "12");
+    assert_eq!(c1.4, 0);
+    c1.4 = 33;
+    assert_eq!(c, c1);
+
+    let c1: Channel = buf.rcv()?;
+    assert_eq!(c2, c1);
+    assert!(buf.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_enum_fields() -> resend::Result<()> {
+    let pdus = [
+        Pdu::Data {
+            channel: 3,
+            payload: vec![1, 2, 3],
+        },
+        Pdu::Ack(7, 8),
+        Pdu::Name {
+            len: 4,
+            name: "abcd".to_string(),
+            extra: Some(9),
+            cached: 0,
+        },
+        Pdu::Name {
+            len: 2,
+            name: "ab".to_string(),
+            extra: None,
+            cached: 0,
+        },
+        Pdu::Padded(3, vec![5, 6, 0]),
+        Pdu::Close,
+    ];
+
+    let mut vec = Vec::new();
+    for pdu in &pdus {
+        vec.snd(pdu)?;
+    }
+    vec.snd(Pdu::Padded(2, vec![5]))?;
+
+    assert_eq!(vec[0], 1);
+    let mut buf = &vec[..];
+    for pdu in &pdus {
+        let p: Pdu = buf.rcv()?;
+        assert_eq!(pdu, &p);
+    }
+    let p: Pdu = buf.rcv()?;
+    assert_eq!(p, Pdu::Padded(2, vec![5, 0]));
+    assert!(buf.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_enum_tag_types() -> resend::Result<()> {
+    let mut vec = Vec::new();
+    vec.snd(&Wide::Big)?;
+    vec.snd(Wide::Next(3))?;
+    assert_eq!(vec.len(), 17);
+    vec.snd(&Signed::Zero)?;
+    vec.snd(Signed::Expr(4))?;
+    vec.snd(&Signed::Shift)?;
+    vec.snd(&Tiny::A)?;
+    vec.snd(Tiny::B(1))?;
+    vec.snd(&Pointer::A)?;
+
+    let mut buf = &vec[..];
+    let v: u64 = buf.rcv()?;
+    assert_eq!(v, 0x1_0000_0001);
+    let v: u64 = buf.rcv()?;
+    assert_eq!(v, 0x1_0000_0002);
+    let _: u8 = buf.rcv()?;
+    let v: i16 = buf.rcv()?;
+    assert_eq!(v, -1);
+    let v: i16 = buf.rcv()?;
+    assert_eq!(v, -600);
+    let _: u8 = buf.rcv()?;
+    let v: i16 = buf.rcv()?;
+    assert_eq!(v, 256);
+    let v: i8 = buf.rcv()?;
+    assert_eq!(v, -128);
+    let v: i8 = buf.rcv()?;
+    assert_eq!(v, -127);
+    let _: u8 = buf.rcv()?;
+    let v: usize = buf.rcv()?;
+    assert_eq!(v, 7);
+
+    let mut buf = &vec[..];
+    assert_eq!(buf.rcv::<Wide>()?, Wide::Big);
+    assert_eq!(buf.rcv::<Wide>()?, Wide::Next(3));
+    assert_eq!(buf.rcv::<Signed>()?, Signed::Zero);
+    assert_eq!(buf.rcv::<Signed>()?, Signed::Expr(4));
+    assert_eq!(buf.rcv::<Signed>()?, Signed::Shift);
+    assert_eq!(buf.rcv::<Tiny>()?, Tiny::A);
+    assert_eq!(buf.rcv::<Tiny>()?, Tiny::B(1));
+    assert_eq!(buf.rcv::<Pointer>()?, Pointer::A);
+    assert!(buf.is_empty());
+
+    let rst = [3_u8, 0].as_ref().rcv::<Signed>();
+    assert!(rst.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_enum_other() -> resend::Result<()> {
+    let mut vec = Vec::new();
+    vec.snd(Message::Hello(3))?;
+    vec.snd(9_u32)?;
+    vec.snd(&Message::Bye)?;
+    vec.snd(&Body::Ping)?;
+    //the trailing Vec<u8> is the rest of the bytes, no Length
+    vec.snd(7_u16)?;
+    vec.extend_from_slice(&[1, 2, 3]);
+
+    let mut buf = &vec[..];
+    assert_eq!(buf.rcv::<Message>()?, Message::Hello(3));
+    let unknown: Message = buf.rcv()?;
+    assert_eq!(unknown, Message::Unknown(9));
+    assert_eq!(buf.rcv::<Message>()?, Message::Bye);
+    assert_eq!(buf.rcv::<Body>()?, Body::Ping);
+    let body: Body = buf.rcv()?;
+    assert_eq!(body, Body::Unknown(7, vec![1, 2, 3]));
+    assert!(buf.is_empty());
+
+    //re-encoded byte-for-byte
+    let mut out = Vec::new();
+    out.snd(Message::Hello(3))?;
+    out.snd(&unknown)?;
+    out.snd(&Message::Bye)?;
+    out.snd(&Body::Ping)?;
+    out.snd(&body)?;
+    assert_eq!(vec, out);
+    Ok(())
+}
+
+#[test]
+fn test_enum_other_rest() -> resend::Result<()> {
+    //unknown tag 3 + u32
+    let mut vec = Vec::new();
+    vec.snd(3_u16)?;
+    vec.snd(0x0102_0304_u32)?;
+    let body: Body = vec.as_slice().rcv()?;
+    assert_eq!(body, Body::Unknown(3, vec[2..].to_vec()));
+    assert_eq!(body.encoded_len(), vec.len());
+    let mut out = Vec::new();
+    out.snd(&body)?;
+    assert_eq!(out, vec);
+
+    //the rest of #[size], the next field is received as usual
+    let v = SizedBody {
+        size: 6,
+        body: Body::Unknown(3, vec[2..].to_vec()),
+        tail: 9,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&v)?;
+    assert_eq!(vec.len(), 8);
+    assert_eq!(v.encoded_len(), vec.len());
+    assert_eq!(vec.as_slice().rcv::<SizedBody>()?, v);
+    Ok(())
+}
+
+//A socket with nothing to read yet
+#[cfg(feature = "std")]
+struct Open<'a>(&'a [u8]);
+
+#[cfg(feature = "std")]
+impl std::io::Read for Open<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(std::io::ErrorKind::WouldBlock.into());
+        }
+        self.0.read(buf)
+    }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_enum_other_open_stream() -> resend::Result<()> {
+    let v = SizedBody {
+        size: 6,
+        body: Body::Unknown(3, vec![1, 2, 3, 4]),
+        tail: 9,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&v)?;
+
+    //#[size] stops at the end of the body
+    let mut stream = Open(&vec);
+    assert_eq!(stream.rcv::<SizedBody>()?, v);
+
+    //the unbounded body waits for the end of the stream
+    assert!(Open(&vec[1..7]).rcv::<Body>().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_enum_field_names() -> resend::Result<()> {
+    let s = Setting::Set {
+        kind: 1,
+        extra: Some(2),
+        condition: 3,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&s)?;
+    assert_eq!(s.encoded_len(), vec.len());
+    assert_eq!(vec.as_slice().rcv::<Setting>()?, s);
+
+    let s = Setting::Marked { tag: 5 };
+    let mut vec = Vec::new();
+    vec.snd(&s)?;
+    assert_eq!(s.encoded_len(), vec.len());
+    assert_eq!(vec.as_slice().rcv::<Setting>()?, s);
+
+    let s = Setting::Tagged {
+        kind: 1,
+        msg: Message::Hello(4),
+        tag: 5,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&s)?;
+    assert_eq!(s.encoded_len(), vec.len());
+    assert_eq!(vec.as_slice().rcv::<Setting>()?, s);
+    Ok(())
+}
+
+#[test]
+fn test_enum_tag_field() -> resend::Result<()> {
+    let pdu = TaggedPdu {
+        header: PduHeader { kind: 1, len: 5 },
+        flags: 3,
+        body: Body::Ping,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&pdu)?;
+    //no tag before the body
+    assert_eq!(vec.len(), 5);
+    assert_eq!(vec.as_slice().rcv::<TaggedPdu>()?, pdu);
+
+    let pdu = TaggedPdu {
+        header: PduHeader { kind: 9, len: 7 },
+        flags: 0,
+        body: Body::Unknown(9, vec![1, 2]),
+    };
+    let mut vec = Vec::new();
+    vec.snd(&pdu)?;
+    assert_eq!(vec.as_slice().rcv::<TaggedPdu>()?, pdu);
+
+    //the tag field doesn't match the body
+    let pdu = TaggedPdu {
+        header: PduHeader { kind: 2, len: 5 },
+        flags: 3,
+        body: Body::Ping,
+    };
+    assert!(matches!(
+        Vec::new().snd(&pdu),
+        Err(resend::error::Error::InvalidTag { ty: "Body", tag: 2 })
+    ));
+
+    //no check: the tag field is trusted
+    let t = TaggedTuple(1, Message::Hello(6));
+    let mut vec = Vec::new();
+    vec.snd(&t)?;
+    assert_eq!(vec.as_slice().rcv::<TaggedTuple>()?, t);
+    Ok(())
+}
+
+#[test]
+fn test_borrowed_struct() -> resend::Result<()> {
+    let f = Frame {
+        len: 3,
+        name: Name {
+            first: "Great",
+            last: "Wall",
+        },
+        data: &[1, 2, 3],
+        color: Color::Red,
+        checksum: Some(9),
+    };
+    let mut vec = Vec::new();
+    vec.snd(&f)?;
+
+    let mut buf = &vec[..];
+    let f1: Frame = buf.rcv_ref()?;
+    assert_eq!(f, f1);
+    assert!(buf.is_empty());
+    //borrowed from the buffer
+    assert!(vec.as_ptr_range().contains(&f1.data.as_ptr()));
+    assert!(vec.as_ptr_range().contains(&f1.name.first.as_ptr()));
+
+    let n = Named {
+        id: 1,
+        name: Name {
+            first: "Great",
+            last: "Wall",
+        },
+    };
+    let mut vec = Vec::new();
+    vec.snd(&n)?;
+    assert_eq!(vec.as_slice().rcv_ref::<Named>()?, n);
+
+    //Receivable, not RefReceivable
+    let s = Shared {
+        value: Cow::Owned(7),
+        marker: PhantomData,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&s)?;
+    let s1: Shared = vec.as_slice().rcv()?;
+    assert_eq!(s1, s);
+    Ok(())
+}
+
+fn check_len<T: EncodedLen>(v: &T) -> resend::Result<()>
+where
+    for<'a> &'a T: Sendable,
+{
+    let mut vec = Vec::new();
+    vec.snd(v)?;
+    assert_eq!(v.encoded_len(), vec.len());
+    Ok(())
+}
+
+#[test]
+fn test_encoded_len() -> resend::Result<()> {
+    let point = Point {
+        x: 3,
+        y: 6,
+        s: "great".to_string(),
+        u: UTF16("a𝌆".to_string()),
+    };
+    check_len(&point)?;
+    check_len(&Person {
+        name: "Great".to_string(),
+        age: 32,
+        qty: 1,
+        senior: Some(true),
+        desc: UTF16("desc".to_string()),
+        ignore: 9,
+        color: Color::Blue,
+        point,
+    })?;
+    check_len(&Channel(ChannelId(1), 4, "abc".to_string(), Some(7), 0))?;
+    check_len(&Channel(ChannelId(1), 2, "abc".to_string(), None, 0))?;
+    check_len(&Empty)?;
+    check_len(&Pdu::Name {
+        len: 3,
+        name: "ab".to_string(),
+        extra: Some(5),
+        cached: 0,
+    })?;
+    check_len(&Pdu::Padded(4, vec![1]))?;
+    check_len(&Pdu::Close)?;
+    check_len(&Wide::Next(1))?;
+    check_len(&Pointer::A)?;
+    check_len(&Message::Unknown(9))?;
+    check_len(&Body::Unknown(7, vec![1, 2, 3]))?;
+    check_len(&TaggedPdu {
+        header: PduHeader { kind: 9, len: 7 },
+        flags: 0,
+        body: Body::Unknown(9, vec![1, 2]),
+    })?;
+    check_len(&Frame {
+        len: 3,
+        name: Name {
+            first: "Great",
+            last: "Wall",
+        },
+        data: &[1, 2, 3],
+        color: Color::Red,
+        checksum: Some(9),
+    })?;
+    Ok(())
+}
+
+#[test]
+fn test_size_field() -> resend::Result<()> {
+    let v = SizedPdu {
+        size: 8,
+        header: PduHeader { kind: 1, len: 2 },
+        name: "ab".to_string(),
+        tail: 9,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&v)?;
+    //padded with 0
+    assert_eq!(vec.len(), 2 + 8 + 6 + 1);
+    assert_eq!(v.encoded_len(), vec.len());
+    assert_eq!(vec.as_slice().rcv::<SizedPdu>()?, v);
+
+    //too large
+    let v = SizedPdu {
+        size: 2,
+        header: PduHeader { kind: 1, len: 2 },
+        name: "ab".to_string(),
+        tail: 9,
+    };
+    assert!(matches!(
+        Vec::new().snd(&v),
+        Err(resend::error::Error::DataTooLarge { len: 4, max: 2 })
+    ));
+
+    //over-read
+    vec[0] = 3;
+    assert!(vec.as_slice().rcv::<SizedPdu>().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_len_expr() -> resend::Result<()> {
+    let v = Record {
+        cb_name: 5,
+        flags: 0x23,
+        name: "abc".to_string(),
+        items: vec![1, 2],
+        header: PduHeader { kind: 1, len: 2 },
+        id: "id".to_string(),
+    };
+    let mut vec = Vec::new();
+    vec.snd(&v)?;
+    assert_eq!(vec.len(), 2 + 1 + 3 + 2 * 2 + 6 + 4);
+    assert_eq!(v.encoded_len(), vec.len());
+    assert_eq!(&vec[3..6], b"abc");
+    //the id is padded with '\0'
+    assert_eq!(&vec[16..], b"id\0\0");
+    assert_eq!(vec.as_slice().rcv::<Record>()?, v);
+
+    let s = Sample(2, vec![1, 2, 3, 4]);
+    let mut vec = Vec::new();
+    vec.snd(&s)?;
+    assert_eq!(vec, [2, 1, 2, 3, 4]);
+    assert_eq!(s.encoded_len(), vec.len());
+    assert_eq!(vec.as_slice().rcv::<Sample>()?, s);
+    Ok(())
+}
+
+#[test]
+fn test_len_of() -> resend::Result<()> {
+    let v = Printer {
+        pnp_name_len: 0,
+        id_count: 0,
+        header_len: 0,
+        pnp_name: UTF16("ab𝌆".to_string()),
+        ids: vec![1, 2, 3],
+        header: PduHeader { kind: 1, len: 2 },
+    };
+    let mut vec = Vec::new();
+    vec.snd(&v)?;
+    assert_eq!(&vec[..7], [8, 0, 0, 0, 0, 3, 4]);
+    assert_eq!(vec.len(), 7 + 8 + 6 + 4);
+    assert_eq!(v.encoded_len(), vec.len());
+    let r: Printer = vec.as_slice().rcv()?;
+    assert_eq!((r.pnp_name_len, r.id_count, r.header_len), (8, 3, 4));
+    assert_eq!((r.pnp_name, r.ids, r.header), (v.pnp_name, v.ids, v.header));
+
+    //#[len] reads elements of Vec, so #[len_of] counts elements too
+    let v = Items {
+        n: 0,
+        items: vec![7, 8],
+    };
+    let mut vec = Vec::new();
+    vec.snd(&v)?;
+    assert_eq!(&vec[..2], [2, 0]);
+    assert_eq!(v.encoded_len(), vec.len());
+    assert_eq!(
+        vec.as_slice().rcv::<Items>()?,
+        Items {
+            n: 2,
+            items: vec![7, 8]
+        }
+    );
+
+    let c = Chunk::Data(0, vec![7, 8]);
+    let mut vec = Vec::new();
+    vec.snd(&c)?;
+    assert_eq!(vec, [0, 2, 7, 8]);
+    assert_eq!(vec.as_slice().rcv::<Chunk>()?, Chunk::Data(2, vec![7, 8]));
+
+    //the length doesn't fit in u8
+    let c = Chunk::Data(0, vec![0; 300]);
+    assert!(matches!(
+        Vec::new().snd(&c),
+        Err(Error::DataTooLarge { len: 300, max: 255 })
+    ));
+
+    //#[when] without the bool, #[varint] as ZigZag
+    for (flag, extra, extra_len) in [(1, Some(7), 4), (0, None, 0)] {
+        let e = Extras {
+            flag,
+            extra_len: 0,
+            v_len: 0,
+            extra,
+            v: 1,
+        };
+        let mut vec = Vec::new();
+        vec.snd(&e)?;
+        assert_eq!(vec[1..3], [extra_len, 1]);
+        assert_eq!(vec.len(), 3 + extra_len as usize + 1);
+        assert_eq!(e.encoded_len(), vec.len());
+        let r: Extras = vec.as_slice().rcv()?;
+        assert_eq!((r.extra_len, r.v_len), (extra_len, 1));
+        assert_eq!((r.extra, r.v), (e.extra, e.v));
+    }
+
+    //#[tag] without the tag
+    let t = TaggedLen {
+        kind: 9,
+        body_len: 0,
+        body: Body::Unknown(9, vec![1, 2]),
+    };
+    let mut vec = Vec::new();
+    vec.snd(&t)?;
+    assert_eq!(vec[2..], [2, 1, 2]);
+    assert_eq!(t.encoded_len(), vec.len());
+    let r: TaggedLen = vec.as_slice().rcv()?;
+    assert_eq!((r.body_len, r.body), (2, t.body));
+    Ok(())
+}
+
+#[test]
+fn test_error_context() -> resend::Result<()> {
+    let point = Point {
+        x: 5,
+        y: 2,
+        s: "great".to_string(),
+        u: UTF16("ok".to_string()),
+    };
+    let p = Person {
+        name: "Great".to_string(),
+        age: 32,
+        qty: 7,
+        senior: Some(true),
+        desc: UTF16("people".to_string()),
+        ignore: 0,
+        color: Color::Green,
+        point,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&p)?;
+    let point_at = p.encoded_len() - p.point.encoded_len();
+
+    //invalid UTF-8 in the nested struct
+    let mut data = vec.clone();
+    data[point_at + 4] = 0xFF;
+    let e = Tracked::new(&data[..]).rcv::<Person>().unwrap_err();
+    assert!(matches!(
+        &e,
+        Error::Context { path, offset: Some(offset), .. } if path == "Person.point.s" && *offset == point_at + 4
+    ));
+    assert!(matches!(e.root(), Error::Utf8(_)));
+
+    //invalid tag
+    let mut data = vec.clone();
+    data[point_at - 2] = 9;
+    let e = Tracked::new(&data[..]).rcv::<Person>().unwrap_err();
+    assert!(matches!(
+        &e,
+        Error::Context { path, offset: Some(offset), .. } if path == "Person.color" && *offset == point_at - 2
+    ));
+
+    //no offset without Tracked
+    let e = data.as_slice().rcv::<Person>().unwrap_err();
+    assert!(matches!(&e, Error::Context { offset: None, .. }));
+
+    //truncated enum variant
+    let mut data = Vec::new();
+    data.snd(DeviceType::Pt(p.point))?;
+    data.truncate(9);
+    let e = Tracked::new(&data[..]).rcv::<DeviceType>().unwrap_err();
+    assert!(matches!(
+        &e,
+        Error::Context { path, offset: Some(8), .. } if path == "DeviceType::Pt.0.s"
+    ));
+
+    //the variant of the nested enum is in the path
+    let mut data = Vec::new();
+    data.snd(1_u8)?;
+    data.snd(DeviceType::Pt(Point {
+        x: 5,
+        y: 2,
+        s: "great".to_string(),
+        u: UTF16("ok".to_string()),
+    }))?;
+    data.truncate(10);
+    let e = Tracked::new(&data[..]).rcv::<Slot>().unwrap_err();
+    assert!(matches!(
+        &e,
+        Error::Context { path, offset: Some(9), .. } if path == "Slot.device::Pt.0.s"
+    ));
+    assert!(e.to_string().starts_with("Slot.device::Pt.0.s @ offset 9"));
+    Ok(())
+}
+
+#[test]
+fn test_error_kinds() -> resend::Result<()> {
+    //the #[when] condition is false but the field is Some
+    let p = Person {
+        name: "Young".to_string(),
+        age: 20,
+        qty: 1,
+        senior: Some(false),
+        desc: UTF16("".to_string()),
+        ignore: 0,
+        color: Color::Red,
+        point: Point {
+            x: 0,
+            y: 0,
+            s: "".to_string(),
+            u: UTF16("".to_string()),
+        },
+    };
+    let e = Vec::new().snd(&p).unwrap_err();
+    assert!(matches!(
+        e,
+        Error::WhenMismatch {
+            field: "senior",
+            condition: false
+        }
+    ));
+    assert_eq!(
+        e.to_string(),
+        "the field senior is Some but its #[when] condition is false"
+    );
+    let e = Vec::new()
+        .snd(&Pdu::Name {
+            len: 3,
+            name: "abc".to_string(),
+            extra: None,
+            cached: 0,
+        })
+        .unwrap_err();
+    assert!(matches!(
+        e,
+        Error::WhenMismatch {
+            field: "extra",
+            condition: true
+        }
+    ));
+
+    let e = [1_u8, 1].as_ref().rcv::<Color>().unwrap_err();
+    assert!(matches!(
+        e,
+        Error::InvalidTag {
+            ty: "Color",
+            tag: 257
+        }
+    ));
+    assert_eq!(e.to_string(), "invalid tag 257 for Color");
+    let e = [0xFF_u8; 8].as_ref().rcv::<Wide>().unwrap_err();
+    assert!(matches!(
+        e,
+        Error::InvalidTag {
+            ty: "Wide",
+            tag: u64::MAX
+        }
+    ));
+
+    let e = Error::custom("checksum mismatch");
+    assert_eq!(e.to_string(), "checksum mismatch");
+    assert!(std::error::Error::source(&e).is_some());
+    Ok(())
+}
+
+#[test]
+fn test_varint() -> resend::Result<()> {
+    let d = Delta {
+        dx: -1,
+        dy: 64,
+        dz: 2,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&d)?;
+    assert_eq!(vec, [1, 0x80, 1, 0x84, 0x80, 0x80, 0]);
+    assert_eq!(d.encoded_len(), vec.len());
+    assert_eq!(vec.as_slice().rcv::<Delta>()?, d);
+
+    for m in [
+        Move::Step(-300),
+        Move::Jump(3, -70000),
+        Move::Jump(10, i64::MIN),
+    ] {
+        let mut vec = Vec::new();
+        vec.snd(&m)?;
+        assert_eq!(m.encoded_len(), vec.len());
+        assert_eq!(vec.as_slice().rcv::<Move>()?, m);
+    }
+    //i64::MIN needs 10 bytes
+    assert!(matches!(
+        Vec::new().snd(Move::Jump(3, i64::MIN)),
+        Err(Error::DataTooLarge { len: 10, max: 3 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_field_byte_order() -> resend::Result<()> {
+    let m = Mixed {
+        kind: 1,
+        id: 0x0102_0304,
+        crc: 0x0506,
+        count: 0x0708,
+        extra: Some(0x090A),
+    };
+    //the plain fields follow the writer, #[be]/#[le] fields don't
+    for order in [ByteOrder::Little, ByteOrder::Big] {
+        let mut vec = Vec::new();
+        ByteOrdered::new(&mut vec, order).snd(&m)?;
+        let count = match order {
+            ByteOrder::Little => [8, 7],
+            ByteOrder::Big => [7, 8],
+        };
+        assert_eq!(&vec[..7], &[1, 1, 2, 3, 4, 6, 5]);
+        assert_eq!(&vec[7..9], &count);
+        assert_eq!(&vec[9..], &[9, 10]);
+        assert_eq!(m.encoded_len(), vec.len());
+
+        let m1: Mixed = ByteOrdered::new(&vec[..], order).rcv()?;
+        assert_eq!(m, m1);
+    }
+
+    let h = NetHeader {
+        version: 2,
+        urgent: true,
+        len: 0x0102,
+        checksum: 0x0304,
+        name: UTF16("a".to_string()),
+    };
+    let mut vec = Vec::new();
+    ByteOrdered::little(&mut vec).snd(&h)?;
+    assert_eq!(&vec[..8], &[2, 1, 0, 0, 1, 2, 4, 3]);
+    assert_eq!(h.encoded_len(), vec.len());
+    assert_eq!(ByteOrdered::little(&vec[..]).rcv::<NetHeader>()?, h);
+
+    let op = Op::Write {
+        addr: 0x0102,
+        value: 0x0304,
+    };
+    let mut vec = Vec::new();
+    ByteOrdered::little(&mut vec).snd(&op)?;
+    //the tag is big-endian too
+    assert_eq!(vec, [0, 2, 1, 2, 4, 3, 0, 0]);
+    assert_eq!(op.encoded_len(), vec.len());
+    assert_eq!(ByteOrdered::little(&vec[..]).rcv::<Op>()?, op);
+    assert_eq!(
+        ByteOrdered::big(&[0_u8, 1, 0, 0, 0, 5][..]).rcv::<Op>()?,
+        Op::Read(5)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_bits() -> resend::Result<()> {
+    let f = IpFragment {
+        flags: 2,
+        offset: 0x0123,
+        ttl: 64,
+    };
+    let mut vec = Vec::new();
+    vec.snd(&f)?;
+    assert_eq!(vec, [0x41, 0x23, 64]);
+    assert_eq!(f.encoded_len(), vec.len());
+    assert_eq!(vec.as_slice().rcv::<IpFragment>()?, f);
+
+    let f = Flags(true, 2, false, 9);
+    let mut vec = Vec::new();
+    vec.snd(&f)?;
+    assert_eq!(vec, [0b101, 9]);
+    assert_eq!(vec.as_slice().rcv::<Flags>()?, f);
+
+    //20 bits in u32, the low 12 bits are 0
+    for p in [Packed::Nibbles { hi: 1, lo: 2 }, Packed::Wide(0xABCDE)] {
+        let mut vec = Vec::new();
+        vec.snd(&p)?;
+        assert_eq!(p.encoded_len(), vec.len());
+        assert_eq!(vec.as_slice().rcv::<Packed>()?, p);
+    }
+    let mut vec = Vec::new();
+    vec.snd(Packed::Wide(0xABCDE))?;
+    assert_eq!(vec, [1, 0xAB, 0xCD, 0xE0, 0]);
+
+    let e = Vec::new()
+        .snd(&IpFragment {
+            flags: 8,
+            offset: 0,
+            ttl: 0,
+        })
+        .unwrap_err();
+    assert!(matches!(
+        e,
+        Error::BitsOverflow {
+            field: "flags",
+            bits: 3
+        }
+    ));
+    assert_eq!(e.to_string(), "the field flags doesn't fit in 3 bits");
+
+    //two's complement: -8..=7 in 4 bits
+    let d = Deltas { dx: -8, dy: -2 };
+    let mut vec = Vec::new();
+    vec.snd(&d)?;
+    assert_eq!(vec.as_slice().rcv::<u16>()?, 0x8FFE);
+    assert_eq!(vec.as_slice().rcv::<Deltas>()?, d);
+    let d = Deltas { dx: 7, dy: 2047 };
+    let mut vec = Vec::new();
+    vec.snd(&d)?;
+    assert_eq!(vec.as_slice().rcv::<Deltas>()?, d);
+    assert!(matches!(
+        Vec::new().snd(&Deltas { dx: 8, dy: 0 }),
+        Err(Error::BitsOverflow {
+            field: "dx",
+            bits: 4
+        })
+    ));
+    assert!(matches!(
+        Vec::new().snd(&Deltas { dx: 0, dy: -2049 }),
+        Err(Error::BitsOverflow {
+            field: "dy",
+            bits: 12
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_derived_in_collections() -> resend::Result<()> {
+    let points = vec![
+        Point {
+            x: 1,
+            y: 0,
+            s: "a".to_string(),
+            u: UTF16(String::new()),
+        },
+        Point {
+            x: 0,
+            y: 2,
+            s: String::new(),
+            u: UTF16("b".to_string()),
+        },
+    ];
+    let colors = VecDeque::from([Color::Blue, Color::Green]);
+    let ids = BTreeMap::from([(1_u8, ChannelId(7))]);
+
+    let mut vec = Vec::new();
+    vec.snd(&points)?;
+    vec.snd(&colors)?;
+    vec.snd(Some(Color::Red))?;
+    vec.snd(&ids)?;
+    vec.snd((Color::Red, ChannelId(9)))?;
+    assert_eq!(
+        vec.len(),
+        points.encoded_len()
+            + colors.encoded_len()
+            + Some(Color::Red).encoded_len()
+            + ids.encoded_len()
+            + 4
+    );
+
+    let mut buf = vec.as_slice();
+    assert_eq!(buf.rcv::<Vec<Point>>()?, points);
+    assert_eq!(buf.rcv::<VecDeque<Color>>()?, colors);
+    assert_eq!(buf.rcv::<Option<Color>>()?, Some(Color::Red));
+    assert_eq!(buf.rcv::<BTreeMap<u8, ChannelId>>()?, ids);
+    assert_eq!(buf.rcv::<(Color, ChannelId)>()?, (Color::Red, ChannelId(9)));
+    assert!(buf.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_generics() -> resend::Result<()> {
+    let e = Envelope {
+        id: 1,
+        body: vec![Color::Red],
+    };
+    let b = Block {
+        kind: 2,
+        data: [3, 4, 5],
+    };
+    let replies: Vec<Reply<u8, String>> = vec![
+        Reply::Ok(6),
+        Reply::Err {
+            code: 7,
+            detail: "no".to_string(),
+        },
+    ];
+    let l = Labeled {
+        label: "x",
+        value: 8_u16,
+    };
+    //not Sendable
+    struct Opaque;
+    let t = Typed::<Opaque> {
+        raw: 9,
+        marker: PhantomData,
+    };
+
+    let mut vec = Vec::new();
+    vec.snd(&e)?;
+    vec.snd(&b)?;
+    vec.snd(&replies)?;
+    vec.snd(&l)?;
+    vec.snd(&t)?;
+    assert_eq!(
+        vec.len(),
+        e.encoded_len()
+            + b.encoded_len()
+            + replies.encoded_len()
+            + l.encoded_len()
+            + t.encoded_len()
+    );
+
+    let mut buf = vec.as_slice();
+    assert_eq!(buf.rcv::<Envelope<Vec<Color>>>()?, e);
+    assert_eq!(buf.rcv::<Block<3>>()?, b);
+    assert_eq!(buf.rcv::<Vec<Reply<u8, String>>>()?, replies);
+    assert_eq!(buf.rcv_ref::<Labeled<u16>>()?, l);
+    assert_eq!(buf.rcv::<Typed<Opaque>>()?.raw, 9);
+    assert!(buf.is_empty());
+    Ok(())
+}
@@ -0,0 +1,9 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+pub struct Flags {
+    #[bits(65)]
+    pub value: u128,
+}
+
+fn main() {}
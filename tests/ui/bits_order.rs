@@ -0,0 +1,12 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+pub struct Flags {
+    #[bits(4)]
+    pub hi: u8,
+    #[le]
+    #[bits(12)]
+    pub lo: u16,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+#[resend(bound = "T Sendable")]
+pub struct Envelope<T> {
+    pub value: T,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use resend_derive::Rcv;
+
+#[derive(Rcv)]
+pub struct Packet {
+    pub len: u16,
+    #[len(len -)]
+    pub data: Vec<u8>,
+}
+
+fn main() {}
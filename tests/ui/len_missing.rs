@@ -0,0 +1,10 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+pub struct Packet {
+    pub len: u16,
+    #[len]
+    pub data: Vec<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+pub struct Flags {
+    #[len_of(hi)]
+    pub len: u8,
+    #[bits(4)]
+    pub hi: u8,
+    #[bits(4)]
+    pub lo: u8,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+pub struct Packet {
+    #[len_of(nmae)]
+    pub name_len: u16,
+    #[len(name_len)]
+    pub name: String,
+}
+
+fn main() {}
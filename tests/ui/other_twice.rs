@@ -0,0 +1,13 @@
+use resend_derive::Rcv;
+
+#[derive(Rcv)]
+#[repr(u8)]
+pub enum Kind {
+    A = 1,
+    #[resend(other)]
+    Unknown(u8),
+    #[resend(other)]
+    Reserved(u8),
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+#[repr(C)]
+pub enum Kind {
+    A = 1,
+    B = 2,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+pub enum Kind {
+    A = 1,
+    B = 300,
+}
+
+fn main() {}
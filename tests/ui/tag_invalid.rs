@@ -0,0 +1,17 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+#[repr(u8)]
+pub enum Body {
+    Ping = 1,
+    Pong = 2,
+}
+
+#[derive(Snd)]
+pub struct Packet {
+    pub kind: u8,
+    #[tag(kind, verify)]
+    pub body: Body,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+pub union Value {
+    pub int: u32,
+    pub float: f32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use resend_derive::Snd;
+
+#[derive(Snd)]
+pub struct Packet {
+    pub flag: bool,
+    #[when(flag value)]
+    pub value: Option<u32>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use resend_derive::Rcv;
+
+#[derive(Rcv)]
+pub struct Packet {
+    pub flag: bool,
+    #[when(flag)]
+    pub value: u32,
+}
+
+fn main() {}